@@ -0,0 +1,439 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * animate.rs - Animated GIF and frame sequence output of the drawing process
+ * ========================================================================
+ */
+
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Transform};
+
+use crate::render::{
+    clip_masks, create_pixmap, draw_fill, draw_segment, skia_color, to_svg, SvgOptions,
+    SvgTimeline, View,
+};
+use crate::turtle::{ProcedureCall, Segment};
+
+use std::fs;
+use std::path::PathBuf;
+
+/**
+ * Length in pixels of the turtle cursor, from its tail to its tip
+ */
+const CURSOR_SIZE: f32 = 10.0;
+
+/**
+ * Represents the user-configurable settings for animated output
+ *
+ * Properties:
+ * animation_path: Option<PathBuf> - The output path of the animated GIF or self-animating SVG, if any
+ * frames_dir: Option<PathBuf> - The output directory of numbered PNG frames, if they should be generated
+ * frame_every: Option<usize> - The number of drawn segments between automatically captured frames, if any
+ * frame_delay: u32 - The time (ms) each frame is shown for, unless set by a WAIT statement
+ * show_cursor: bool - Whether to draw the turtle cursor on top of each frame
+ * speed: f64 - The speed (pixels per second) at which lines are drawn in a self-animating SVG
+ */
+#[derive(Debug, Clone)]
+pub struct AnimationOptions {
+    pub animation_path: Option<PathBuf>,
+    pub frames_dir: Option<PathBuf>,
+    pub frame_every: Option<usize>,
+    pub frame_delay: u32,
+    pub show_cursor: bool,
+    pub speed: f64,
+}
+
+impl AnimationOptions {
+    pub fn enabled(&self) -> bool {
+        self.animation_path.is_some() || self.frames_dir.is_some()
+    }
+}
+
+/**
+ * Represents a point during the drawing at which the canvas is captured
+ *
+ * Properties:
+ * segments: usize - The number of recorded segments drawn onto the canvas in this frame
+ * x: f64 - The x-coordinate of the turtle at the time of capture
+ * y: f64 - The y-coordinate of the turtle at the time of capture
+ * heading: f64 - The heading of the turtle at the time of capture
+ * color: i32 - The pen color of the turtle at the time of capture
+ * delay: Option<u32> - The time (ms) the frame is shown for, if it overrides the default
+ * automatic: bool - Whether the frame was captured automatically rather than by a FRAME/WAIT statement
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameMarker {
+    pub segments: usize,
+    pub x: f64,
+    pub y: f64,
+    pub heading: f64,
+    pub color: i32,
+    pub delay: Option<u32>,
+    pub automatic: bool,
+}
+
+/**
+ * Draw the turtle cursor as a triangle pointing in the direction of its heading
+ */
+fn draw_cursor(pixmap: &mut Pixmap, marker: &FrameMarker, transform: Transform) {
+    let (x, y) = (marker.x as f32, marker.y as f32);
+    let angle = (marker.heading as f32).to_radians();
+
+    // Headings start at 0 degrees pointing up and go clockwise
+    let point = |forward: f32, side: f32| {
+        (
+            x + forward * angle.sin() + side * angle.cos(),
+            y - forward * angle.cos() + side * angle.sin(),
+        )
+    };
+
+    let tip = point(CURSOR_SIZE / 2.0, 0.0);
+    let left = point(-CURSOR_SIZE / 2.0, -CURSOR_SIZE / 3.0);
+    let right = point(-CURSOR_SIZE / 2.0, CURSOR_SIZE / 3.0);
+
+    let mut path = PathBuilder::new();
+    path.move_to(tip.0, tip.1);
+    path.line_to(left.0, left.1);
+    path.line_to(right.0, right.1);
+    path.close();
+
+    if let Some(path) = path.finish() {
+        let mut paint = Paint::default();
+        paint.set_color(skia_color(marker.color));
+
+        pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+    }
+}
+
+/**
+ * Create a looping animated GIF encoder for a canvas of the given size
+ */
+fn create_gif_encoder(
+    path: &PathBuf,
+    width: u32,
+    height: u32,
+) -> Result<gif::Encoder<fs::File>, String> {
+    let width = u16::try_from(width).map_err(|_| "canvas is too wide for a GIF".to_string())?;
+    let height = u16::try_from(height).map_err(|_| "canvas is too tall for a GIF".to_string())?;
+
+    let file = fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = gif::Encoder::new(file, width, height, &[]).map_err(|e| e.to_string())?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|e| e.to_string())?;
+
+    Ok(encoder)
+}
+
+/**
+ * Append a single frame, shown for the given time (ms), to an animated GIF
+ */
+fn write_gif_frame(
+    encoder: &mut gif::Encoder<fs::File>,
+    frame: &Pixmap,
+    delay: u32,
+) -> Result<(), String> {
+    // The canvas is always opaque, so its premultiplied pixels are already plain RGBA
+    let mut pixels = frame.data().to_vec();
+    let mut gif_frame =
+        gif::Frame::from_rgba_speed(frame.width() as u16, frame.height() as u16, &mut pixels, 10);
+
+    // GIF delays are measured in hundredths of a second
+    gif_frame.delay = u16::try_from(delay / 10).unwrap_or(u16::MAX);
+
+    encoder.write_frame(&gif_frame).map_err(|e| e.to_string())
+}
+
+/**
+ * Generate the animated outputs requested in the animation options
+ *
+ * The canvas is drawn incrementally and every frame is written out as soon as it is captured, so each segment is
+ * only rasterised once and only a single frame is held in memory at a time. Gradient fills appear once their FILLED
+ * block has finished, underneath the lines drawn inside it, so the canvas is redrawn whenever one appears
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * calls: &[ProcedureCall] - The procedure calls the segments were made in
+ * markers: &[FrameMarker] - The points at which the canvas is captured, in drawing order
+ * view: &View - The region of the canvas to show
+ * svg_options: &SvgOptions - The precision, compactness and source mapping of a self-animating SVG
+ * options: &AnimationOptions - Where to write the animation and how to render it
+ *
+ * Returns:
+ * Result<(), String> - Nothing, or a description of the problem
+ */
+pub fn save_animation(
+    segments: &[Segment],
+    calls: &[ProcedureCall],
+    markers: &[FrameMarker],
+    view: &View,
+    svg_options: &SvgOptions,
+    options: &AnimationOptions,
+) -> Result<(), String> {
+    let mut encoder = None;
+
+    if let Some(path) = &options.animation_path {
+        match path.extension().and_then(|s| s.to_str()) {
+            Some("gif") => {
+                encoder = Some(create_gif_encoder(
+                    path,
+                    view.output_width,
+                    view.output_height,
+                )?)
+            }
+            Some("svg") => {
+                if options.speed <= 0.0 {
+                    return Err(format!("speed must be positive, got {}", options.speed));
+                }
+
+                let svg = to_animated_svg(segments, view, calls, svg_options, options.speed);
+                fs::write(path, svg).map_err(|e| e.to_string())?;
+            }
+            _ => return Err("animation file extension must be either .gif or .svg".to_string()),
+        }
+    }
+
+    if encoder.is_none() && options.frames_dir.is_none() {
+        return Ok(());
+    }
+
+    let mut canvas = create_pixmap(view)?;
+    let masks = clip_masks(view)?;
+    let transform = view.transform();
+
+    if let Some(dir) = &options.frames_dir {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    let mut drawn = 0;
+    let mut fills_drawn = 0;
+    let mut fills_shown = 0;
+
+    for (i, marker) in markers.iter().enumerate() {
+        let fills: Vec<_> = view
+            .fills
+            .iter()
+            .filter(|fill| fill.finished <= marker.segments)
+            .collect();
+
+        if fills.len() != fills_shown {
+            canvas = create_pixmap(view)?;
+            drawn = 0;
+            fills_drawn = 0;
+            fills_shown = fills.len();
+        }
+
+        for (j, segment) in segments
+            .iter()
+            .enumerate()
+            .take(marker.segments)
+            .skip(drawn)
+        {
+            while fills_drawn < fills.len() && fills[fills_drawn].segments <= j {
+                draw_fill(&mut canvas, fills[fills_drawn], transform, &masks);
+                fills_drawn += 1;
+            }
+
+            if segment.pen_down {
+                draw_segment(&mut canvas, segment, view.background, transform, &masks);
+            }
+        }
+        drawn = marker.segments;
+
+        for fill in &fills[fills_drawn..] {
+            draw_fill(&mut canvas, fill, transform, &masks);
+        }
+        fills_drawn = fills.len();
+
+        let cursor_frame;
+        let frame = if options.show_cursor {
+            let mut frame = canvas.clone();
+            draw_cursor(&mut frame, marker, transform);
+            cursor_frame = frame;
+            &cursor_frame
+        } else {
+            &canvas
+        };
+
+        if let Some(dir) = &options.frames_dir {
+            frame
+                .save_png(dir.join(format!("frame_{:05}.png", i)))
+                .map_err(|e| e.to_string())?;
+        }
+
+        if let Some(encoder) = &mut encoder {
+            write_gif_frame(encoder, frame, marker.delay.unwrap_or(options.frame_delay))?;
+        }
+    }
+
+    Ok(())
+}
+
+/*
+ * Self-animating SVG
+ */
+
+/**
+ * Generate an SVG in which the lines appear in the order the turtle drew them
+ *
+ * The elements and attributes are identical to the static SVG output (see `to_svg`), with an added SMIL animation
+ * of each line's `stroke-dasharray`, so viewers without animation support (and the end of the animation) show the
+ * final drawing. Each line takes as long to appear as it takes to draw it at the given speed. Gradient fills appear
+ * as soon as their FILLED block has finished
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * view: &View - The region of the canvas to show
+ * calls: &[ProcedureCall] - The procedure calls the segments were made in
+ * svg_options: &SvgOptions - The precision, compactness and source mapping of the output
+ * speed: f64 - The speed (pixels per second) at which lines are drawn
+ *
+ * Returns:
+ * String - The SVG file contents
+ */
+pub fn to_animated_svg(
+    segments: &[Segment],
+    view: &View,
+    calls: &[ProcedureCall],
+    svg_options: &SvgOptions,
+    speed: f64,
+) -> String {
+    let timeline = SvgTimeline::new(segments, speed);
+
+    to_svg(segments, view, calls, svg_options, Some(&timeline))
+}
+
+/*
+ * Unit tests
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_view() -> View {
+        View {
+            x: 0.0,
+            y: 0.0,
+            width: 20.0,
+            height: 20.0,
+            output_width: 20,
+            output_height: 20,
+            background: 0,
+            underlay: None,
+            clips: vec![],
+            fills: vec![],
+        }
+    }
+
+    fn test_marker(segments: usize, delay: Option<u32>) -> FrameMarker {
+        FrameMarker {
+            segments,
+            x: 0.0,
+            y: 0.0,
+            heading: 0.0,
+            color: 7,
+            delay,
+            automatic: false,
+        }
+    }
+
+    /**
+     * A directory of its own for each test's output, emptied first
+     */
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rslogo-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn test_options(
+        animation_path: Option<PathBuf>,
+        frames_dir: Option<PathBuf>,
+    ) -> AnimationOptions {
+        AnimationOptions {
+            animation_path,
+            frames_dir,
+            frame_every: None,
+            frame_delay: 100,
+            show_cursor: false,
+            speed: 100.0,
+        }
+    }
+
+    /*
+     * Frames
+     */
+    #[test]
+    fn test_save_animation_frames() {
+        let dir = test_dir("frames");
+        let segments = vec![
+            Segment::test_line(5.5, 5.5, 15.5, 5.5, true, 7),
+            Segment::test_line(15.5, 5.5, 15.5, 15.5, true, 7),
+        ];
+        let markers = vec![
+            test_marker(0, None),
+            test_marker(1, None),
+            test_marker(2, None),
+        ];
+        let svg_options = SvgOptions {
+            precision: 3,
+            compact: false,
+            source_map: false,
+        };
+        let options = test_options(None, Some(dir.clone()));
+
+        save_animation(
+            &segments,
+            &[],
+            &markers,
+            &test_view(),
+            &svg_options,
+            &options,
+        )
+        .unwrap();
+
+        // Each frame shows the lines drawn so far
+        let white = skia_color(7).to_color_u8().premultiply();
+        let expected = [(false, false), (true, false), (true, true)];
+        for (i, &(first, second)) in expected.iter().enumerate() {
+            let frame = Pixmap::load_png(dir.join(format!("frame_{:05}.png", i))).unwrap();
+
+            assert_eq!(frame.pixel(10, 5) == Some(white), first);
+            assert_eq!(frame.pixel(15, 10) == Some(white), second);
+        }
+        assert!(!dir.join("frame_00003.png").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_animation_gif() {
+        let dir = test_dir("gif");
+        let path = dir.join("animation.gif");
+        let segments = vec![Segment::test_line(5.0, 5.0, 15.0, 5.0, true, 7)];
+        let markers = vec![test_marker(0, Some(u32::MAX)), test_marker(1, None)];
+        let svg_options = SvgOptions {
+            precision: 3,
+            compact: false,
+            source_map: false,
+        };
+        let options = test_options(Some(path.clone()), None);
+
+        save_animation(
+            &segments,
+            &[],
+            &markers,
+            &test_view(),
+            &svg_options,
+            &options,
+        )
+        .unwrap();
+
+        let gif = fs::read(&path).unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
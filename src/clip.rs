@@ -0,0 +1,136 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * clip.rs - Clipping of drawn lines to SETCLIP/CLIPPOLYGON regions
+ * ========================================================================
+ */
+
+use crate::turtle::Segment;
+
+/**
+ * Whether a point lies inside a polygon, using the nonzero winding rule (as SVG clip paths do by default)
+ */
+fn contains(polygon: &[(f64, f64)], (x, y): (f64, f64)) -> bool {
+    let mut winding = 0;
+
+    for i in 0..polygon.len() {
+        let (x1, y1) = polygon[i];
+        let (x2, y2) = polygon[(i + 1) % polygon.len()];
+
+        // Which side of the edge the point is on, positive if to the left
+        let side = (x2 - x1) * (y - y1) - (x - x1) * (y2 - y1);
+
+        if y1 <= y && y < y2 && side > 0.0 {
+            winding += 1;
+        } else if y2 <= y && y < y1 && side < 0.0 {
+            winding -= 1;
+        }
+    }
+
+    winding != 0
+}
+
+/**
+ * The parts of a straight line from p to q that lie inside a polygon, as fractions (start, end) of the way along it
+ */
+pub fn clip_line(polygon: &[(f64, f64)], p: (f64, f64), q: (f64, f64)) -> Vec<(f64, f64)> {
+    let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+
+    // The line can only cross into or out of the polygon where it meets one of its edges
+    let mut crossings = vec![0.0, 1.0];
+    for i in 0..polygon.len() {
+        let (x1, y1) = polygon[i];
+        let (x2, y2) = polygon[(i + 1) % polygon.len()];
+        let (ex, ey) = (x2 - x1, y2 - y1);
+
+        let denominator = dx * ey - dy * ex;
+        if denominator == 0.0 {
+            continue;
+        }
+
+        let t = ((x1 - p.0) * ey - (y1 - p.1) * ex) / denominator;
+        let u = ((x1 - p.0) * dy - (y1 - p.1) * dx) / denominator;
+
+        if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+            crossings.push(t);
+        }
+    }
+    crossings.sort_by(f64::total_cmp);
+    crossings.dedup();
+
+    let mut parts: Vec<(f64, f64)> = Vec::new();
+    for pair in crossings.windows(2) {
+        let middle = (pair[0] + pair[1]) / 2.0;
+
+        if contains(polygon, (p.0 + dx * middle, p.1 + dy * middle)) {
+            match parts.last_mut() {
+                Some(last) if last.1 == pair[0] => last.1 = pair[1],
+                _ => parts.push((pair[0], pair[1])),
+            }
+        }
+    }
+
+    parts
+}
+
+/**
+ * Cut every drawn line down to the clipping region it was drawn in, for output formats without clipping support
+ *
+ * Lines and curves entirely inside their region are kept as they are. Curves that cross the edge of their region
+ * are split into short straight lines first. The returned segments have no clipping region
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * clips: &[Vec<(f64, f64)>] - The clipping regions the segments refer to
+ *
+ * Returns:
+ * Vec<Segment> - The clipped movements, in drawing order
+ */
+pub fn clip_segments(segments: &[Segment], clips: &[Vec<(f64, f64)>]) -> Vec<Segment> {
+    let mut clipped = Vec::with_capacity(segments.len());
+
+    for segment in segments {
+        let polygon = match segment.clip {
+            Some(index) if segment.pen_down => &clips[index],
+            _ => {
+                clipped.push(Segment {
+                    clip: None,
+                    ..segment.clone()
+                });
+                continue;
+            }
+        };
+
+        let lines = segment.lines();
+        let parts: Vec<Vec<(f64, f64)>> = lines
+            .iter()
+            .map(|line| clip_line(polygon, (line.x1, line.y1), (line.x2, line.y2)))
+            .collect();
+
+        if parts.iter().all(|part| part[..] == [(0.0, 1.0)]) {
+            clipped.push(Segment {
+                clip: None,
+                ..segment.clone()
+            });
+            continue;
+        }
+
+        for (line, part) in lines.iter().zip(parts) {
+            let (dx, dy) = (line.x2 - line.x1, line.y2 - line.y1);
+
+            for (start, end) in part {
+                clipped.push(Segment {
+                    x1: line.x1 + dx * start,
+                    y1: line.y1 + dy * start,
+                    x2: line.x1 + dx * end,
+                    y2: line.y1 + dy * end,
+                    clip: None,
+                    ..line.clone()
+                });
+            }
+        }
+    }
+
+    clipped
+}
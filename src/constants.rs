@@ -26,6 +26,24 @@ pub type Block = Vec<Statement>;
 #[derive(Debug, PartialEq, Clone)]
 pub struct Identifier(pub String, pub String);
 
+/**
+ * A rewrite rule of an L-system
+ *
+ * Replaces every occurrence of a symbol with a string of symbols in each iteration. Symbols with several rules pick
+ * one at random in proportion to their weights
+ *
+ * The argument structure is as follows:
+ * symbol: char - The symbol to be rewritten
+ * weight: u32 - The relative likelihood of this rule being picked among the rules for the same symbol
+ * replacement: String - The symbols that replace it
+ */
+#[derive(Debug, PartialEq, Clone)]
+pub struct LSystemRule {
+    pub symbol: char,
+    pub weight: u32,
+    pub replacement: String,
+}
+
 /**
  * An expression in the Logo language
  *
@@ -72,6 +90,7 @@ pub enum Expression {
     QueryYCor,
     QueryHeading,
     QueryColor,
+    QueryPenMode,
 
     /*
      * Terminal values
@@ -92,11 +111,19 @@ pub enum Expression {
  */
 #[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
+    /*
+     * Source line marker, placed by the parser before every statement it parses from a program
+     */
+    SourceLine(usize),
+
     /*
      * Pen control
      */
     PenUp,
     PenDown,
+    PenPaint,
+    PenErase,
+    PenReverse,
 
     /*
      * Movement control
@@ -107,6 +134,12 @@ pub enum Statement {
     Right(Box<Expression>),
     Turn(Box<Expression>),
 
+    /*
+     * Curves
+     */
+    CurveTo(Vec<Expression>),
+    Spline(Vec<Expression>),
+
     /*
      * Setters
      */
@@ -114,6 +147,21 @@ pub enum Statement {
     SetY(Box<Expression>),
     SetHeading(Box<Expression>),
     SetPenColor(Box<Expression>),
+    SetCoords(Box<Expression>),
+    SetBackground(Box<Expression>),
+
+    /*
+     * Boundary modes
+     */
+    Wrap,
+    Fence,
+    Window,
+
+    /*
+     * State stack
+     */
+    PushState,
+    PopState,
 
     /*
      * Variable assignment
@@ -128,6 +176,80 @@ pub enum Statement {
     While(Box<Expression>, Box<Block>),
     Repeat(Box<Expression>, Box<Block>),
 
+    /*
+     * Animation
+     */
+    Frame,
+    Wait(Box<Expression>),
+
+    /*
+     * Transforms
+     */
+    Scale {
+        factor: Box<Expression>,
+        divisor: Option<Box<Expression>>,
+    },
+    RotateCanvas(Box<Expression>),
+    Mirror(Box<Expression>),
+    WithTransform(Box<Block>),
+
+    /*
+     * Fills
+     */
+    SetFillPattern {
+        pattern: Box<Expression>,
+        angle: Box<Expression>,
+        spacing: Box<Expression>,
+    },
+    Filled(Box<Block>),
+
+    /*
+     * Gradients
+     */
+    SetPenGradient {
+        from: Box<Expression>,
+        to: Box<Expression>,
+        steps: Box<Expression>,
+    },
+    SetFillGradient {
+        kind: Box<Expression>,
+        from: Box<Expression>,
+        to: Box<Expression>,
+        angle: Option<Box<Expression>>,
+    },
+
+    /*
+     * Clipping
+     */
+    SetClip(Vec<Expression>),
+    ClipPolygon(Vec<Expression>),
+    NoClip,
+
+    /*
+     * Symmetry
+     */
+    Symmetry(Box<Expression>),
+
+    /*
+     * Shapes
+     */
+    SetShape(Box<Expression>),
+    SetShapePoints(Vec<Expression>),
+    Stamp,
+    ShowTurtle,
+    HideTurtle,
+
+    /*
+     * L-systems
+     */
+    LSystem {
+        iterations: Box<Expression>,
+        angle: Box<Expression>,
+        distance: Box<Expression>,
+        axiom: String,
+        rules: Vec<LSystemRule>,
+    },
+
     /*
      * Procedures
      */
@@ -8,7 +8,12 @@
 
 use crate::constants::{Block, Expression, Statement};
 use crate::error::{debug, print_error};
-use crate::turtle::Turtle;
+use crate::fill::{FillGradient, FillPattern};
+use crate::lsystem;
+use crate::shape::TurtleShape;
+use crate::stats::ExecutionCounts;
+use crate::turtle::{check_pen_color, BoundaryMode, CoordinateSystem, PenMode, Symmetry, Turtle};
+use crate::utils::Rng;
 
 use std::collections::HashMap;
 
@@ -27,7 +32,19 @@ use std::collections::HashMap;
 struct ExpressionValue {
     value_type: String,
     integer_value: Option<i32>,
-    string_value: Option<String>, // This value is never read, but that is by design, as it is only used for storage
+    string_value: Option<String>,
+}
+
+impl ExpressionValue {
+    /**
+     * Whether two values of the same type are equal, with strings compared ignoring case
+     */
+    fn equals(&self, other: &ExpressionValue) -> bool {
+        match (&self.string_value, &other.string_value) {
+            (Some(left), Some(right)) => left.eq_ignore_ascii_case(right),
+            _ => self.integer_value == other.integer_value,
+        }
+    }
 }
 
 /**
@@ -36,12 +53,16 @@ struct ExpressionValue {
  * Properties:
  * turtle: Turtle - The turtle object representing the relative cursor position and state
  * variables: HashMap<String, ExpressionValue> - A hashmap of variable names to their evaluated values
+ * rng: Rng - The seeded random number generator
+ * counts: ExecutionCounts - The number of statements executed and procedures called so far
  */
 #[derive(Debug)]
 struct ProgramState {
     turtle: Turtle,
     stack: Vec<(String, Option<ExpressionValue>)>,
     procedures: HashMap<String, (Vec<String>, Block)>,
+    rng: Rng,
+    counts: ExecutionCounts,
 }
 
 impl ProgramState {
@@ -113,12 +134,15 @@ impl ProgramState {
  * Arguments:
  * turtle: Turtle - The turtle object representing the relative cursor position and state
  * ast: Vec<Statement> - The abstract syntax tree representing the program contents, a seuqence of statements
+ * seed: u64 - The seed of the random number generator
  */
-pub fn evaluate_program(turtle: Turtle, ast: Vec<Statement>) {
+pub fn evaluate_program(turtle: Turtle, ast: Vec<Statement>, seed: u64) {
     let mut state = ProgramState {
         turtle,
         stack: Vec::new(),
         procedures: HashMap::new(),
+        rng: Rng::new(seed),
+        counts: ExecutionCounts::default(),
     };
 
     debug("fully parsed ast", &format!("{:#?}", ast));
@@ -127,7 +151,11 @@ pub fn evaluate_program(turtle: Turtle, ast: Vec<Statement>) {
     evaluate_ast(&ast, &mut state);
     debug("final program state", &format!("{:#?}", state));
 
+    state.turtle.draw_visible_turtle();
     state.turtle.generate_svg();
+    state.turtle.generate_animation();
+    state.turtle.generate_tty();
+    state.turtle.generate_stats(&state.counts);
 }
 
 /**
@@ -137,7 +165,16 @@ fn evaluate_ast(ast: &Block, state: &mut ProgramState) {
     for node in ast {
         debug("intermediate program state", &format!("{:#?}", state));
 
+        // Source line markers are added by the parser, not written in the program
+        if !matches!(node, Statement::SourceLine(_)) {
+            state.counts.statements += 1;
+        }
+
         match node {
+            Statement::SourceLine(line) => {
+                state.turtle.set_source_line(*line);
+            }
+
             /*
              * Pen control
              */
@@ -147,6 +184,15 @@ fn evaluate_ast(ast: &Block, state: &mut ProgramState) {
             Statement::PenDown => {
                 state.turtle.pendown();
             }
+            Statement::PenPaint => {
+                state.turtle.set_pen_mode(PenMode::Paint);
+            }
+            Statement::PenErase => {
+                state.turtle.set_pen_mode(PenMode::Erase);
+            }
+            Statement::PenReverse => {
+                state.turtle.set_pen_mode(PenMode::Reverse);
+            }
 
             /*
              * Movement control
@@ -172,6 +218,45 @@ fn evaluate_ast(ast: &Block, state: &mut ProgramState) {
                 state.turtle.turn(angle.integer_value.unwrap() as f64);
             }
 
+            /*
+             * Curves
+             */
+            Statement::CurveTo(exprs) => {
+                let values: Vec<f64> = exprs
+                    .iter()
+                    .map(|expr| evaluate_expression(expr, state).integer_value.unwrap() as f64)
+                    .collect();
+
+                // Points are given as (forward, right) distances from the turtle
+                state.turtle.curve_to(
+                    (values[0], values[1]),
+                    (values[2], values[3]),
+                    (values[4], values[5]),
+                );
+            }
+            Statement::Spline(exprs) => {
+                let values: Vec<f64> = exprs
+                    .iter()
+                    .map(|expr| evaluate_expression(expr, state).integer_value.unwrap() as f64)
+                    .collect();
+
+                if values.is_empty() || !values.len().is_multiple_of(2) {
+                    print_error(
+                        "invalid spline",
+                        &format!(
+                            "a spline needs pairs of forward and right distances, got {} values",
+                            values.len()
+                        ),
+                        &["list at least one point as \"forward \"right pairs"],
+                        true,
+                    );
+                }
+
+                let points: Vec<(f64, f64)> =
+                    values.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+                state.turtle.spline(&points);
+            }
+
             /*
              * Setters
              */
@@ -191,9 +276,54 @@ fn evaluate_ast(ast: &Block, state: &mut ProgramState) {
             }
             Statement::SetPenColor(expr) => {
                 let color = evaluate_expression(expr, state);
-                state
-                    .turtle
-                    .set_pen_color(color.integer_value.unwrap() as i32);
+                state.turtle.set_pen_color(color.integer_value.unwrap());
+            }
+            Statement::SetBackground(expr) => {
+                let color = evaluate_expression(expr, state);
+                state.turtle.set_background(color.integer_value.unwrap());
+            }
+            Statement::SetCoords(expr) => {
+                let system = evaluate_expression(expr, state);
+                let name = system.string_value.unwrap_or_default();
+
+                match name.to_lowercase().as_str() {
+                    "screen" => state.turtle.set_coordinate_system(CoordinateSystem::Screen),
+                    "cartesian" => state
+                        .turtle
+                        .set_coordinate_system(CoordinateSystem::Cartesian),
+                    _ => print_error(
+                        "invalid coordinate system",
+                        &format!(
+                            "coordinate system must be either screen or cartesian, got {}",
+                            name
+                        ),
+                        &["use SETCOORDS \"screen or SETCOORDS \"cartesian"],
+                        true,
+                    ),
+                }
+            }
+
+            /*
+             * Boundary modes
+             */
+            Statement::Wrap => {
+                state.turtle.set_boundary_mode(BoundaryMode::Wrap);
+            }
+            Statement::Fence => {
+                state.turtle.set_boundary_mode(BoundaryMode::Fence);
+            }
+            Statement::Window => {
+                state.turtle.set_boundary_mode(BoundaryMode::Window);
+            }
+
+            /*
+             * State stack
+             */
+            Statement::PushState => {
+                state.turtle.push_state();
+            }
+            Statement::PopState => {
+                state.turtle.pop_state();
             }
 
             /*
@@ -243,6 +373,379 @@ fn evaluate_ast(ast: &Block, state: &mut ProgramState) {
                 }
             }
 
+            /*
+             * Animation
+             */
+            Statement::Frame => {
+                state.turtle.capture_frame(None);
+            }
+            Statement::Wait(expr) => {
+                let ticks = evaluate_expression(expr, state).integer_value.unwrap();
+
+                if ticks < 0 {
+                    print_error(
+                        "invalid wait",
+                        &format!("wait time must not be negative, got {}", ticks),
+                        &["ensure the wait time is a positive number of ticks (1/60ths of a second)"],
+                        true,
+                    );
+                }
+
+                // Logo measures waiting time in 60ths of a second, and very long waits are capped
+                let delay = u32::try_from(ticks as u64 * 1000 / 60).unwrap_or(u32::MAX);
+                state.turtle.capture_frame(Some(delay));
+            }
+
+            /*
+             * Transforms
+             */
+            Statement::Scale { factor, divisor } => {
+                let factor = evaluate_expression(factor, state).integer_value.unwrap();
+                let divisor = match divisor {
+                    Some(divisor) => evaluate_expression(divisor, state).integer_value.unwrap(),
+                    None => 1,
+                };
+
+                if factor == 0 {
+                    print_error(
+                        "invalid scale",
+                        "scale factor must not be zero",
+                        &["use PENUP to move without drawing instead"],
+                        true,
+                    );
+                }
+                if divisor == 0 {
+                    print_error(
+                        "invalid scale",
+                        "scale divisor must not be zero",
+                        &["use SCALE \"1 \"2 to halve the size of everything drawn"],
+                        true,
+                    );
+                }
+
+                let transform = state
+                    .turtle
+                    .transform()
+                    .scaled(factor as f64 / divisor as f64);
+                state.turtle.set_transform(transform);
+            }
+            Statement::RotateCanvas(expr) => {
+                let degrees = evaluate_expression(expr, state).integer_value.unwrap();
+
+                let transform = state.turtle.transform().rotated(degrees as f64);
+                state.turtle.set_transform(transform);
+            }
+            Statement::Mirror(expr) => {
+                let axis = evaluate_expression(expr, state)
+                    .string_value
+                    .unwrap_or_default();
+
+                let horizontal = match axis.to_lowercase().as_str() {
+                    "x" => true,
+                    "y" => false,
+                    _ => {
+                        print_error(
+                            "invalid mirror axis",
+                            &format!("mirror axis must be either x or y, got {}", axis),
+                            &["use MIRROR \"x to flip left and right or MIRROR \"y to flip up and down"],
+                            true,
+                        );
+                        return;
+                    }
+                };
+
+                let transform = state.turtle.transform().mirrored(horizontal);
+                state.turtle.set_transform(transform);
+            }
+            Statement::WithTransform(block) => {
+                // Transforms made inside the block are undone when it finishes
+                let transform = state.turtle.transform();
+                evaluate_ast(block, state);
+                state.turtle.set_transform(transform);
+            }
+
+            /*
+             * Fills
+             */
+            Statement::SetFillPattern {
+                pattern,
+                angle,
+                spacing,
+            } => {
+                let name = evaluate_expression(pattern, state)
+                    .string_value
+                    .unwrap_or_default();
+                let angle = evaluate_expression(angle, state).integer_value.unwrap();
+                let spacing = evaluate_expression(spacing, state).integer_value.unwrap();
+
+                if spacing < 1 {
+                    print_error(
+                        "invalid fill spacing",
+                        &format!("fill spacing must be at least 1 pixel, got {}", spacing),
+                        &["ensure the spacing between lines or dots is a positive number"],
+                        true,
+                    );
+                }
+
+                match FillPattern::from_name(&name, angle as f64, spacing as f64) {
+                    Some(pattern) => state.turtle.set_fill_pattern(pattern),
+                    None => print_error(
+                        "invalid fill pattern",
+                        &format!(
+                            "fill pattern must be one of hatch, crosshatch or dots, got {}",
+                            name
+                        ),
+                        &["use e.g. SETFILLPATTERN \"hatch \"45 \"5"],
+                        true,
+                    ),
+                }
+            }
+            Statement::Filled(block) => {
+                state.turtle.begin_fill();
+                evaluate_ast(block, state);
+                state.turtle.end_fill();
+            }
+
+            /*
+             * Gradients
+             */
+            Statement::SetPenGradient { from, to, steps } => {
+                let from = evaluate_expression(from, state).integer_value.unwrap();
+                let to = evaluate_expression(to, state).integer_value.unwrap();
+                let steps = evaluate_expression(steps, state).integer_value.unwrap();
+
+                if steps < 1 {
+                    print_error(
+                        "invalid gradient steps",
+                        &format!("a gradient must take at least 1 step, got {}", steps),
+                        &["ensure the number of steps is a positive number"],
+                        true,
+                    );
+                }
+
+                state.turtle.set_pen_gradient(from, to, steps as u32);
+            }
+            Statement::SetFillGradient {
+                kind,
+                from,
+                to,
+                angle,
+            } => {
+                let name = evaluate_expression(kind, state)
+                    .string_value
+                    .unwrap_or_default();
+                let from = evaluate_expression(from, state).integer_value.unwrap();
+                let to = evaluate_expression(to, state).integer_value.unwrap();
+                let angle = match angle {
+                    Some(angle) => evaluate_expression(angle, state).integer_value.unwrap(),
+                    None => 0,
+                };
+
+                check_pen_color(from);
+                check_pen_color(to);
+
+                match FillGradient::from_name(&name, from, to, angle as f64) {
+                    Some(gradient) => state
+                        .turtle
+                        .set_fill_pattern(FillPattern::Gradient(gradient)),
+                    None => print_error(
+                        "invalid fill gradient",
+                        &format!("fill gradient must be linear or radial, got {}", name),
+                        &["use e.g. SETFILLGRADIENT \"linear \"1 \"4 \"90"],
+                        true,
+                    ),
+                }
+            }
+
+            /*
+             * Clipping
+             */
+            Statement::SetClip(exprs) => {
+                let values: Vec<f64> = exprs
+                    .iter()
+                    .map(|expr| evaluate_expression(expr, state).integer_value.unwrap() as f64)
+                    .collect();
+
+                if values.len() != 4 {
+                    print_error(
+                        "invalid clipping rectangle",
+                        &format!(
+                            "a clipping rectangle needs the x and y of two opposite corners, got {} values",
+                            values.len()
+                        ),
+                        &["use SETCLIP [x1 y1 x2 y2]", "use CLIPPOLYGON for other shapes"],
+                        true,
+                    );
+                }
+
+                let (x1, y1, x2, y2) = (values[0], values[1], values[2], values[3]);
+                state
+                    .turtle
+                    .set_clip(&[(x1, y1), (x2, y1), (x2, y2), (x1, y2)]);
+            }
+            Statement::ClipPolygon(exprs) => {
+                let values: Vec<f64> = exprs
+                    .iter()
+                    .map(|expr| evaluate_expression(expr, state).integer_value.unwrap() as f64)
+                    .collect();
+
+                if values.len() < 6 || !values.len().is_multiple_of(2) {
+                    print_error(
+                        "invalid clipping polygon",
+                        &format!(
+                            "a clipping polygon needs at least three pairs of x and y coordinates, got {} values",
+                            values.len()
+                        ),
+                        &["list the corners of the polygon as \"x \"y pairs"],
+                        true,
+                    );
+                }
+
+                let corners: Vec<(f64, f64)> =
+                    values.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+                state.turtle.set_clip(&corners);
+            }
+            Statement::NoClip => {
+                state.turtle.clear_clip();
+            }
+
+            /*
+             * Symmetry
+             */
+            Statement::Symmetry(expr) => {
+                let value = evaluate_expression(expr, state);
+
+                let symmetry = match (value.integer_value, value.string_value) {
+                    (Some(order), _) if order >= 1 => Symmetry::Rotational(order as u32),
+                    (_, Some(mode)) if mode.eq_ignore_ascii_case("mirror") => Symmetry::Mirror,
+                    (_, Some(mode)) if mode.eq_ignore_ascii_case("off") => Symmetry::Rotational(1),
+                    _ => {
+                        print_error(
+                            "invalid symmetry",
+                            "symmetry must be a positive number of copies, mirror or off",
+                            &[
+                                "use SYMMETRY \"6 for six copies around the centre of the canvas",
+                                "use SYMMETRY \"mirror for a left-to-right reflection",
+                                "use SYMMETRY \"off or SYMMETRY \"1 to stop replicating lines",
+                            ],
+                            true,
+                        );
+                        return;
+                    }
+                };
+
+                state.turtle.set_symmetry(symmetry);
+            }
+
+            /*
+             * Shapes
+             */
+            Statement::SetShape(expr) => {
+                let name = evaluate_expression(expr, state)
+                    .string_value
+                    .unwrap_or_default();
+
+                match TurtleShape::from_name(&name) {
+                    Some(shape) => state.turtle.set_shape(shape),
+                    None => print_error(
+                        "invalid shape",
+                        &format!(
+                            "shape must be one of arrow, turtle, circle or square, got {}",
+                            name
+                        ),
+                        &[
+                            "use SETSHAPE \"arrow, \"turtle, \"circle or \"square",
+                            "give a custom shape as a list of points, e.g. SETSHAPE [\"10 \"0 \"-5 \"5 \"-5 \"-5]",
+                        ],
+                        true,
+                    ),
+                }
+            }
+            Statement::SetShapePoints(exprs) => {
+                let values: Vec<f64> = exprs
+                    .iter()
+                    .map(|expr| evaluate_expression(expr, state).integer_value.unwrap() as f64)
+                    .collect();
+
+                if values.len() < 4 || !values.len().is_multiple_of(2) {
+                    print_error(
+                        "invalid shape",
+                        &format!(
+                            "a custom shape needs at least two pairs of forward and right distances, got {} values",
+                            values.len()
+                        ),
+                        &["list the corners of the shape as \"forward \"right pairs"],
+                        true,
+                    );
+                }
+
+                let points = values.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+                state.turtle.set_shape(TurtleShape::Custom(points));
+            }
+            Statement::Stamp => {
+                state.turtle.stamp();
+            }
+            Statement::ShowTurtle => {
+                state.turtle.set_visible(true);
+            }
+            Statement::HideTurtle => {
+                state.turtle.set_visible(false);
+            }
+
+            /*
+             * L-systems
+             */
+            Statement::LSystem {
+                iterations,
+                angle,
+                distance,
+                axiom,
+                rules,
+            } => {
+                let iterations = evaluate_expression(iterations, state)
+                    .integer_value
+                    .unwrap();
+                let angle = evaluate_expression(angle, state).integer_value.unwrap();
+                let distance = evaluate_expression(distance, state).integer_value.unwrap();
+
+                if axiom.is_empty() {
+                    print_error(
+                        "missing axiom",
+                        "an L-system must have an AXIOM to start from",
+                        &["add an AXIOM line inside the LSYSTEM block"],
+                        true,
+                    );
+                }
+
+                if iterations < 0 {
+                    print_error(
+                        "invalid iteration count",
+                        &format!("iteration count must not be negative, got {}", iterations),
+                        &["ensure the iteration count is zero or more"],
+                        true,
+                    );
+                }
+
+                let symbols = match lsystem::expand(axiom, rules, iterations as u32, &mut state.rng)
+                {
+                    Ok(symbols) => symbols,
+                    Err(error) => {
+                        print_error(
+                            "L-system too large",
+                            &error,
+                            &[
+                                "reduce the number of iterations",
+                                "use rules with shorter replacements",
+                            ],
+                            true,
+                        );
+                        panic!();
+                    }
+                };
+
+                lsystem::draw(&mut state.turtle, &symbols, angle as f64, distance as f64);
+            }
+
             /*
              * Procedures
              */
@@ -290,6 +793,12 @@ fn evaluate_ast(ast: &Block, state: &mut ProgramState) {
                     }
                 };
 
+                *state
+                    .counts
+                    .procedure_calls
+                    .entry(name.0.clone())
+                    .or_default() += 1;
+
                 let (parameters, body) = procedure;
                 let parameters_len = parameters.len();
                 let mut parameters_pushed = 0;
@@ -327,7 +836,9 @@ fn evaluate_ast(ast: &Block, state: &mut ProgramState) {
                     parameters_pushed += 1;
                 }
 
+                state.turtle.enter_procedure(&name.0);
                 evaluate_ast(&body, state);
+                state.turtle.exit_procedure();
 
                 while parameters_pushed > 0 {
                     state.pop();
@@ -421,6 +932,19 @@ fn evaluate_expression(expr: &Expression, state: &mut ProgramState) -> Expressio
             integer_value: Some(state.turtle.color()),
             string_value: None,
         },
+        // There are no string comparisons, so pen modes are reported as 0 (paint), 1 (erase) or 2 (reverse)
+        Expression::QueryPenMode => ExpressionValue {
+            value_type: "string".to_string(),
+            integer_value: None,
+            string_value: Some(
+                match state.turtle.pen_mode() {
+                    PenMode::Paint => "PAINT",
+                    PenMode::Erase => "ERASE",
+                    PenMode::Reverse => "REVERSE",
+                }
+                .to_string(),
+            ),
+        },
 
         /*
          * Arithmetic operators
@@ -557,15 +1081,12 @@ fn evaluate_expression(expr: &Expression, state: &mut ProgramState) -> Expressio
             let left = evaluate_expression(lhs, state);
             let right = evaluate_expression(rhs, state);
 
-            if left.value_type != "integer" || right.value_type != "integer" {
+            if left.value_type != right.value_type {
                 print_error_type_mismatch("equality");
             }
 
-            let new_value = if left.integer_value.unwrap() == right.integer_value.unwrap() {
-                1
-            } else {
-                0
-            };
+            // Strings (e.g. from the PENMODE query) can be compared too, as in EQ PENMODE "erase
+            let new_value = if left.equals(&right) { 1 } else { 0 };
 
             ExpressionValue {
                 value_type: "integer".to_string(),
@@ -577,15 +1098,11 @@ fn evaluate_expression(expr: &Expression, state: &mut ProgramState) -> Expressio
             let left = evaluate_expression(lhs, state);
             let right = evaluate_expression(rhs, state);
 
-            if left.value_type != "integer" || right.value_type != "integer" {
+            if left.value_type != right.value_type {
                 print_error_type_mismatch("inequality");
             }
 
-            let new_value = if left.integer_value.unwrap() != right.integer_value.unwrap() {
-                1
-            } else {
-                0
-            };
+            let new_value = if !left.equals(&right) { 1 } else { 0 };
 
             ExpressionValue {
                 value_type: "integer".to_string(),
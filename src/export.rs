@@ -0,0 +1,887 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * export.rs - Vector export formats generated from the turtle's segments
+ * ========================================================================
+ */
+
+use crate::stats::StatsFormat;
+use crate::terminal::TtyMode;
+use crate::turtle::{palette_rgb, PenMode, Segment};
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/**
+ * Number of millimetres in a single SVG pixel (1/96th of an inch)
+ *
+ * Used as the default physical scale when no explicit plot size is given
+ */
+const MM_PER_PIXEL: f64 = 25.4 / 96.0;
+
+/**
+ * Represents where the origin (0, 0) of the machine coordinates lies on the drawing
+ *
+ * Machine coordinates always have the y-axis pointing upwards, unlike the canvas
+ */
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Origin {
+    TopLeft,
+    BottomLeft,
+    Center,
+}
+
+/**
+ * Represents the user-configurable settings for the output
+ *
+ * Covers every output format (SVG and PNG included), the canvas they show, and the drawing and statistics printed to
+ * the terminal
+ *
+ * Properties:
+ * feed_rate: f64 - The speed (mm/min) at which lines are drawn with the pen down
+ * travel_rate: f64 - The speed (mm/min) at which the pen travels while lifted
+ * pen_up_z: f64 - The Z height (mm) of the pen when lifted
+ * pen_down_z: f64 - The Z height (mm) of the pen when drawing
+ * plot_size: Option<(f64, f64)> - The physical area (mm) the drawing is scaled to fit, if any
+ * origin: Origin - Where the machine origin lies on the drawing
+ * optimize_paths: bool - Whether lines are reordered to reduce pen-up travel in plotter and page output
+ * page: Option<PageSize> - The paper size for page-based output, if not the size of the canvas
+ * tty: Option<TtyMode> - The character set to print the drawing to the terminal with, if it should be printed
+ * stats: Option<StatsFormat> - The format to print statistics about the drawing in, if they should be printed
+ * fit: bool - Whether SVG/PNG output is zoomed to the bounding box of the drawing
+ * infinite: bool - Whether SVG/PNG output is resized to the bounding box of the drawing
+ * margin: f64 - The space (pixels) left around the drawing when fitting or resizing to it
+ * background: i32 - The color (0-15) of the canvas background
+ * underlay: Option<PathBuf> - The PNG image drawn underneath SVG/PNG output, if any
+ * compact_svg: bool - Whether connected lines are joined into polylines grouped by colour in SVG output
+ * precision: usize - The number of decimal places coordinates of lines are written with in SVG output
+ * source_map: bool - Whether SVG elements are tagged with the source line and procedure that drew them
+ */
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub feed_rate: f64,
+    pub travel_rate: f64,
+    pub pen_up_z: f64,
+    pub pen_down_z: f64,
+    pub plot_size: Option<(f64, f64)>,
+    pub origin: Origin,
+    pub optimize_paths: bool,
+    pub page: Option<PageSize>,
+    pub tty: Option<TtyMode>,
+    pub stats: Option<StatsFormat>,
+    pub fit: bool,
+    pub infinite: bool,
+    pub margin: f64,
+    pub background: i32,
+    pub underlay: Option<PathBuf>,
+    pub compact_svg: bool,
+    pub precision: usize,
+    pub source_map: bool,
+}
+
+/**
+ * Parse a physical plot size of the form `WIDTHxHEIGHT` (in millimetres)
+ *
+ * Used as a value parser for the command line arguments
+ *
+ * Arguments:
+ * input: &str - The size string, e.g. "210x297"
+ *
+ * Returns:
+ * Result<(f64, f64), String> - The width and height in millimetres, or a description of the problem
+ */
+pub fn parse_plot_size(input: &str) -> Result<(f64, f64), String> {
+    let (width, height) = input
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got {}", input))?;
+
+    let width: f64 = width
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid width {}", width))?;
+    let height: f64 = height
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid height {}", height))?;
+
+    if width <= 0.0 || height <= 0.0 {
+        return Err("plot size must be positive".to_string());
+    }
+
+    Ok((width, height))
+}
+
+/**
+ * Maps canvas pixel coordinates to physical machine coordinates
+ *
+ * The drawing is scaled uniformly so that it fits inside the plot size (if given), and the y-axis is flipped
+ */
+struct PhysicalMapping {
+    scale: f64,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+impl PhysicalMapping {
+    fn new(width: u32, height: u32, options: &ExportOptions) -> Self {
+        let (width, height) = (width as f64, height as f64);
+
+        let scale = match options.plot_size {
+            Some((plot_width, plot_height)) => (plot_width / width).min(plot_height / height),
+            None => MM_PER_PIXEL,
+        };
+
+        let (offset_x, offset_y) = match options.origin {
+            Origin::TopLeft => (0.0, 0.0),
+            Origin::BottomLeft => (0.0, height),
+            Origin::Center => (width / 2.0, height / 2.0),
+        };
+
+        PhysicalMapping {
+            scale,
+            offset_x,
+            offset_y,
+        }
+    }
+
+    fn map(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.offset_x) * self.scale,
+            (self.offset_y - y) * self.scale,
+        )
+    }
+}
+
+/*
+ * G-code
+ */
+
+/**
+ * Generate G-code for a pen plotter from the turtle's segments
+ *
+ * Pen-down segments are drawn at the feed rate with the pen lowered; the pen is lifted and moved
+ * at the travel rate whenever the next line does not start where the previous one ended
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * width: u32 - The width of the canvas in pixels
+ * height: u32 - The height of the canvas in pixels
+ * options: &ExportOptions - The feed rates, pen heights, size and origin to use
+ *
+ * Returns:
+ * String - The G-code program
+ */
+pub fn to_gcode(segments: &[Segment], width: u32, height: u32, options: &ExportOptions) -> String {
+    let mapping = PhysicalMapping::new(width, height, options);
+    let mut output = String::new();
+
+    let _ = writeln!(output, "; generated by rslogo");
+    let _ = writeln!(output, "G21 ; millimetres");
+    let _ = writeln!(output, "G90 ; absolute positioning");
+    let _ = writeln!(output, "G0 Z{:.3}", options.pen_up_z);
+
+    let mut position: Option<(f64, f64)> = None;
+    let mut pen_lowered = false;
+
+    for segment in segments
+        .iter()
+        .filter(|segment| segment.is_painted())
+        .flat_map(Segment::lines)
+    {
+        let start = (segment.x1, segment.y1);
+
+        if position != Some(start) {
+            if pen_lowered {
+                let _ = writeln!(output, "G0 Z{:.3}", options.pen_up_z);
+                pen_lowered = false;
+            }
+
+            let (x, y) = mapping.map(segment.x1, segment.y1);
+            let _ = writeln!(output, "G0 X{:.3} Y{:.3} F{:.0}", x, y, options.travel_rate);
+        }
+
+        if !pen_lowered {
+            let _ = writeln!(
+                output,
+                "G1 Z{:.3} F{:.0}",
+                options.pen_down_z, options.feed_rate
+            );
+            pen_lowered = true;
+        }
+
+        let (x, y) = mapping.map(segment.x2, segment.y2);
+        let _ = writeln!(output, "G1 X{:.3} Y{:.3} F{:.0}", x, y, options.feed_rate);
+
+        position = Some((segment.x2, segment.y2));
+    }
+
+    let _ = writeln!(output, "G0 Z{:.3}", options.pen_up_z);
+    let _ = writeln!(output, "G0 X0 Y0 F{:.0}", options.travel_rate);
+    let _ = writeln!(output, "M2");
+
+    output
+}
+
+/*
+ * HPGL
+ */
+
+/**
+ * Number of HPGL plotter units in a millimetre
+ */
+const HPGL_UNITS_PER_MM: f64 = 40.0;
+
+/**
+ * Generate HPGL for a pen plotter or cutter from the turtle's segments
+ *
+ * Each of the 16 pen colours is mapped to a pen number (colour 0 is pen 1, colour 15 is pen 16), and a new pen is
+ * selected whenever the colour changes
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * width: u32 - The width of the canvas in pixels
+ * height: u32 - The height of the canvas in pixels
+ * options: &ExportOptions - The size and origin to use
+ *
+ * Returns:
+ * String - The HPGL program
+ */
+pub fn to_hpgl(segments: &[Segment], width: u32, height: u32, options: &ExportOptions) -> String {
+    let mapping = PhysicalMapping::new(width, height, options);
+    let to_units = |x: f64, y: f64| {
+        let (x, y) = mapping.map(x, y);
+        (
+            (x * HPGL_UNITS_PER_MM).round() as i64,
+            (y * HPGL_UNITS_PER_MM).round() as i64,
+        )
+    };
+
+    let mut output = String::new();
+    output.push_str("IN;\n");
+
+    let mut position: Option<(f64, f64)> = None;
+    let mut pen: Option<i32> = None;
+
+    for segment in segments
+        .iter()
+        .filter(|segment| segment.is_painted())
+        .flat_map(Segment::lines)
+    {
+        if pen != Some(segment.color) {
+            let _ = writeln!(output, "SP{};", segment.color + 1);
+            pen = Some(segment.color);
+        }
+
+        if position != Some((segment.x1, segment.y1)) {
+            let (x, y) = to_units(segment.x1, segment.y1);
+            let _ = writeln!(output, "PU{},{};", x, y);
+        }
+
+        let (x, y) = to_units(segment.x2, segment.y2);
+        let _ = writeln!(output, "PD{},{};", x, y);
+
+        position = Some((segment.x2, segment.y2));
+    }
+
+    output.push_str("PU;\nSP0;\n");
+
+    output
+}
+
+/*
+ * DXF
+ */
+
+/**
+ * AutoCAD Color Index equivalents of the 16 pen colours in `unsvg::COLORS`
+ */
+const DXF_COLOR_INDICES: [i32; 16] = [7, 5, 4, 3, 1, 6, 2, 7, 14, 42, 94, 131, 11, 214, 30, 8];
+
+/**
+ * Generate a DXF drawing for CAD tools from the turtle's segments
+ *
+ * Every drawn segment becomes a LINE entity on the layer of its pen colour (`PEN_0` to `PEN_15`)
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * width: u32 - The width of the canvas in pixels
+ * height: u32 - The height of the canvas in pixels
+ * options: &ExportOptions - The size and origin to use
+ *
+ * Returns:
+ * String - The DXF file contents
+ */
+pub fn to_dxf(segments: &[Segment], width: u32, height: u32, options: &ExportOptions) -> String {
+    let mapping = PhysicalMapping::new(width, height, options);
+    let mut output = String::new();
+
+    // Group codes and values always alternate on separate lines
+    let mut group = |code: i32, value: &str| {
+        let _ = writeln!(output, "{}\n{}", code, value);
+    };
+
+    group(0, "SECTION");
+    group(2, "HEADER");
+    group(9, "$INSUNITS");
+    group(70, "4"); // Millimetres
+    group(0, "ENDSEC");
+
+    group(0, "SECTION");
+    group(2, "TABLES");
+    group(0, "TABLE");
+    group(2, "LAYER");
+    group(70, &DXF_COLOR_INDICES.len().to_string());
+    for (color, index) in DXF_COLOR_INDICES.iter().enumerate() {
+        group(0, "LAYER");
+        group(2, &format!("PEN_{}", color));
+        group(70, "0");
+        group(62, &index.to_string());
+        group(6, "CONTINUOUS");
+    }
+    group(0, "ENDTAB");
+    group(0, "ENDSEC");
+
+    group(0, "SECTION");
+    group(2, "ENTITIES");
+    for segment in segments
+        .iter()
+        .filter(|segment| segment.is_painted())
+        .flat_map(Segment::lines)
+    {
+        let (x1, y1) = mapping.map(segment.x1, segment.y1);
+        let (x2, y2) = mapping.map(segment.x2, segment.y2);
+
+        group(0, "LINE");
+        group(8, &format!("PEN_{}", segment.color));
+        group(10, &format!("{:.3}", x1));
+        group(20, &format!("{:.3}", y1));
+        group(30, "0.0");
+        group(11, &format!("{:.3}", x2));
+        group(21, &format!("{:.3}", y2));
+        group(31, "0.0");
+    }
+    group(0, "ENDSEC");
+    group(0, "EOF");
+
+    output
+}
+
+/*
+ * PDF and EPS
+ */
+
+/**
+ * Number of PostScript points in a single SVG pixel (72 points and 96 pixels per inch)
+ */
+const POINTS_PER_PIXEL: f64 = 72.0 / 96.0;
+
+/**
+ * Represents the standard paper sizes available for page-based output
+ */
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum PageSize {
+    A3,
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PageSize {
+    /**
+     * The portrait dimensions of the paper size in points
+     */
+    fn dimensions(&self) -> (f64, f64) {
+        match self {
+            PageSize::A3 => (841.89, 1190.55),
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::A5 => (419.53, 595.28),
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::Legal => (612.0, 1008.0),
+        }
+    }
+}
+
+/**
+ * Places the canvas on a page measured in points
+ *
+ * Without an explicit paper size the page is exactly the size of the canvas. Otherwise the paper is turned to match
+ * the orientation of the canvas, and the canvas is scaled to fit and centred on it
+ */
+struct PageLayout {
+    page_width: f64,
+    page_height: f64,
+    scale: f64,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+impl PageLayout {
+    fn new(width: u32, height: u32, options: &ExportOptions) -> Self {
+        let (width, height) = (width as f64, height as f64);
+
+        match options.page {
+            None => PageLayout {
+                page_width: width * POINTS_PER_PIXEL,
+                page_height: height * POINTS_PER_PIXEL,
+                scale: POINTS_PER_PIXEL,
+                offset_x: 0.0,
+                offset_y: 0.0,
+            },
+            Some(page) => {
+                let (short, long) = page.dimensions();
+                let (page_width, page_height) = if width > height {
+                    (long, short)
+                } else {
+                    (short, long)
+                };
+
+                let scale = (page_width / width).min(page_height / height);
+
+                PageLayout {
+                    page_width,
+                    page_height,
+                    scale,
+                    offset_x: (page_width - width * scale) / 2.0,
+                    offset_y: (page_height - height * scale) / 2.0,
+                }
+            }
+        }
+    }
+
+    /**
+     * The components of the transformation matrix from canvas pixels to page points (whose y-axis points upwards)
+     */
+    fn matrix(&self) -> [f64; 6] {
+        [
+            self.scale,
+            0.0,
+            0.0,
+            -self.scale,
+            self.offset_x,
+            self.page_height - self.offset_y,
+        ]
+    }
+}
+
+/**
+ * The components of a colour given as (red, green, blue) as fractions between 0 and 1
+ */
+fn rgb_fractions((red, green, blue): (u8, u8, u8)) -> (f64, f64, f64) {
+    (
+        red as f64 / 255.0,
+        green as f64 / 255.0,
+        blue as f64 / 255.0,
+    )
+}
+
+/**
+ * Generate a single-page PDF document from the turtle's segments
+ *
+ * The canvas background and pen colours match the SVG output, and lines keep the 1 pixel width used there
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * width: u32 - The width of the canvas in pixels
+ * height: u32 - The height of the canvas in pixels
+ * options: &ExportOptions - The paper size to use, if any
+ *
+ * Returns:
+ * Vec<u8> - The PDF file contents
+ */
+pub fn to_pdf(segments: &[Segment], width: u32, height: u32, options: &ExportOptions) -> Vec<u8> {
+    let layout = PageLayout::new(width, height, options);
+    let [a, b, c, d, e, f] = layout.matrix();
+
+    let mut content = String::new();
+    let _ = writeln!(
+        content,
+        "{:.4} {:.4} {:.4} {:.4} {:.4} {:.4} cm",
+        a, b, c, d, e, f
+    );
+    let (red, green, blue) = rgb_fractions(palette_rgb(options.background));
+    let _ = writeln!(
+        content,
+        "{:.3} {:.3} {:.3} rg 0 0 {} {} re f",
+        red, green, blue, width, height
+    );
+    let _ = writeln!(content, "1 w 0 J 0 j");
+
+    let mut pen: Option<(u8, u8, u8)> = None;
+    for segment in segments.iter().filter(|segment| segment.pen_down) {
+        if pen != Some(segment.stroke_rgb(options.background)) {
+            let (red, green, blue) = rgb_fractions(segment.stroke_rgb(options.background));
+            let _ = writeln!(content, "{:.3} {:.3} {:.3} RG", red, green, blue);
+            pen = Some(segment.stroke_rgb(options.background));
+        }
+
+        // Reversing lines are drawn with a difference blend, which inverts whatever is underneath them
+        let line = match segment.controls {
+            Some((cx1, cy1, cx2, cy2)) => format!(
+                "{} {} m {} {} {} {} {} {} c S",
+                segment.x1, segment.y1, cx1, cy1, cx2, cy2, segment.x2, segment.y2
+            ),
+            None => format!(
+                "{} {} m {} {} l S",
+                segment.x1, segment.y1, segment.x2, segment.y2
+            ),
+        };
+        match segment.mode {
+            PenMode::Reverse => {
+                let _ = writeln!(content, "q /Reverse gs {} Q", line);
+            }
+            PenMode::Paint | PenMode::Erase => {
+                let _ = writeln!(content, "{}", line);
+            }
+        }
+    }
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Contents 4 0 R /Resources << /ExtGState << /Reverse << /BM /Difference >> >> >> >>",
+            layout.page_width, layout.page_height
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            content.len(),
+            content
+        ),
+    ];
+
+    let mut output = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(output.len());
+        let _ = writeln!(output, "{} 0 obj\n{}\nendobj", i + 1, object);
+    }
+
+    // Every cross-reference entry must be exactly 20 bytes long
+    let xref_offset = output.len();
+    let _ = writeln!(output, "xref\n0 {}", objects.len() + 1);
+    output.push_str("0000000000 65535 f \n");
+    for offset in offsets {
+        let _ = writeln!(output, "{:010} 00000 n ", offset);
+    }
+
+    let _ = writeln!(
+        output,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF",
+        objects.len() + 1,
+        xref_offset
+    );
+
+    output.into_bytes()
+}
+
+/**
+ * Generate an Encapsulated PostScript file from the turtle's segments
+ *
+ * The canvas background and pen colours match the SVG output, and lines keep the 1 pixel width used there
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * width: u32 - The width of the canvas in pixels
+ * height: u32 - The height of the canvas in pixels
+ * options: &ExportOptions - The paper size to use, if any
+ *
+ * Returns:
+ * String - The EPS file contents
+ */
+pub fn to_eps(segments: &[Segment], width: u32, height: u32, options: &ExportOptions) -> String {
+    let layout = PageLayout::new(width, height, options);
+    let [a, b, c, d, e, f] = layout.matrix();
+
+    let mut output = String::new();
+    let _ = writeln!(output, "%!PS-Adobe-3.0 EPSF-3.0");
+    let _ = writeln!(output, "%%Creator: rslogo");
+    let _ = writeln!(
+        output,
+        "%%BoundingBox: 0 0 {} {}",
+        layout.page_width.ceil(),
+        layout.page_height.ceil()
+    );
+    let _ = writeln!(
+        output,
+        "%%HiResBoundingBox: 0 0 {:.2} {:.2}",
+        layout.page_width, layout.page_height
+    );
+    let _ = writeln!(output, "%%EndComments");
+
+    let _ = writeln!(output, "gsave");
+    let _ = writeln!(
+        output,
+        "[{:.4} {:.4} {:.4} {:.4} {:.4} {:.4}] concat",
+        a, b, c, d, e, f
+    );
+    let (red, green, blue) = rgb_fractions(palette_rgb(options.background));
+    let _ = writeln!(
+        output,
+        "{:.3} {:.3} {:.3} setrgbcolor 0 0 {} {} rectfill",
+        red, green, blue, width, height
+    );
+    let _ = writeln!(output, "1 setlinewidth 0 setlinecap 0 setlinejoin");
+
+    // PostScript has no blend modes, so reversing lines are drawn plainly in white
+    let mut pen: Option<(u8, u8, u8)> = None;
+    for segment in segments.iter().filter(|segment| segment.pen_down) {
+        if pen != Some(segment.stroke_rgb(options.background)) {
+            let (red, green, blue) = rgb_fractions(segment.stroke_rgb(options.background));
+            let _ = writeln!(output, "{:.3} {:.3} {:.3} setrgbcolor", red, green, blue);
+            pen = Some(segment.stroke_rgb(options.background));
+        }
+
+        let _ = match segment.controls {
+            Some((cx1, cy1, cx2, cy2)) => writeln!(
+                output,
+                "newpath {} {} moveto {} {} {} {} {} {} curveto stroke",
+                segment.x1, segment.y1, cx1, cy1, cx2, cy2, segment.x2, segment.y2
+            ),
+            None => writeln!(
+                output,
+                "newpath {} {} moveto {} {} lineto stroke",
+                segment.x1, segment.y1, segment.x2, segment.y2
+            ),
+        };
+    }
+
+    let _ = writeln!(output, "grestore");
+    let _ = writeln!(output, "showpage");
+    let _ = writeln!(output, "%%EOF");
+
+    output
+}
+
+/*
+ * Unit tests
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Options plotting a 100x100 canvas at 1 mm per pixel, with the origin at the bottom left
+     */
+    fn test_options() -> ExportOptions {
+        ExportOptions {
+            feed_rate: 1500.0,
+            travel_rate: 3000.0,
+            pen_up_z: 5.0,
+            pen_down_z: 0.0,
+            plot_size: Some((100.0, 100.0)),
+            origin: Origin::BottomLeft,
+            optimize_paths: false,
+            page: None,
+            tty: None,
+            stats: None,
+            fit: false,
+            infinite: false,
+            margin: 0.0,
+            background: 0,
+            underlay: None,
+            compact_svg: false,
+            precision: 3,
+            source_map: false,
+        }
+    }
+
+    /**
+     * Two connected lines in blue, a move with the pen up, an erased line and a line in cyan
+     */
+    fn test_segments() -> Vec<Segment> {
+        let mut erased = Segment::test_line(50.0, 50.0, 50.0, 40.0, true, 1);
+        erased.mode = PenMode::Erase;
+
+        vec![
+            Segment::test_line(10.0, 90.0, 30.0, 90.0, true, 1),
+            Segment::test_line(30.0, 90.0, 30.0, 70.0, true, 1),
+            Segment::test_line(30.0, 70.0, 50.0, 50.0, false, 1),
+            erased,
+            Segment::test_line(50.0, 50.0, 60.0, 50.0, true, 2),
+        ]
+    }
+
+    /*
+     * G-code
+     */
+    #[test]
+    fn test_to_gcode() {
+        let expected = [
+            "; generated by rslogo",
+            "G21 ; millimetres",
+            "G90 ; absolute positioning",
+            "G0 Z5.000",
+            "G0 X10.000 Y10.000 F3000",
+            "G1 Z0.000 F1500",
+            "G1 X30.000 Y10.000 F1500",
+            "G1 X30.000 Y30.000 F1500",
+            "G0 Z5.000",
+            "G0 X50.000 Y50.000 F3000",
+            "G1 Z0.000 F1500",
+            "G1 X60.000 Y50.000 F1500",
+            "G0 Z5.000",
+            "G0 X0 Y0 F3000",
+            "M2\n",
+        ]
+        .join("\n");
+        let result = to_gcode(&test_segments(), 100, 100, &test_options());
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_to_gcode_empty() {
+        let expected = [
+            "; generated by rslogo",
+            "G21 ; millimetres",
+            "G90 ; absolute positioning",
+            "G0 Z5.000",
+            "G0 Z5.000",
+            "G0 X0 Y0 F3000",
+            "M2\n",
+        ]
+        .join("\n");
+        let result = to_gcode(&[], 100, 100, &test_options());
+
+        assert_eq!(result, expected);
+    }
+
+    /*
+     * HPGL
+     */
+    #[test]
+    fn test_to_hpgl() {
+        let expected = [
+            "IN;",
+            "SP2;",
+            "PU400,400;",
+            "PD1200,400;",
+            "PD1200,1200;",
+            "SP3;",
+            "PU2000,2000;",
+            "PD2400,2000;",
+            "PU;",
+            "SP0;\n",
+        ]
+        .join("\n");
+        let result = to_hpgl(&test_segments(), 100, 100, &test_options());
+
+        assert_eq!(result, expected);
+    }
+
+    /*
+     * DXF
+     */
+    #[test]
+    fn test_to_dxf_header() {
+        let expected = concat!(
+            "0\nSECTION\n",
+            "2\nHEADER\n",
+            "9\n$INSUNITS\n",
+            "70\n4\n",
+            "0\nENDSEC\n",
+            "0\nSECTION\n",
+            "2\nTABLES\n",
+            "0\nTABLE\n",
+            "2\nLAYER\n",
+            "70\n16\n",
+            "0\nLAYER\n",
+            "2\nPEN_0\n",
+            "70\n0\n",
+            "62\n7\n",
+            "6\nCONTINUOUS\n",
+        );
+        let result = to_dxf(&test_segments(), 100, 100, &test_options());
+
+        assert!(result.starts_with(expected));
+    }
+
+    #[test]
+    fn test_to_dxf_entities() {
+        let expected = concat!(
+            "0\nSECTION\n",
+            "2\nENTITIES\n",
+            "0\nLINE\n",
+            "8\nPEN_1\n",
+            "10\n10.000\n",
+            "20\n10.000\n",
+            "30\n0.0\n",
+            "11\n30.000\n",
+            "21\n10.000\n",
+            "31\n0.0\n",
+            "0\nLINE\n",
+            "8\nPEN_1\n",
+            "10\n30.000\n",
+            "20\n10.000\n",
+            "30\n0.0\n",
+            "11\n30.000\n",
+            "21\n30.000\n",
+            "31\n0.0\n",
+            "0\nLINE\n",
+            "8\nPEN_2\n",
+            "10\n50.000\n",
+            "20\n50.000\n",
+            "30\n0.0\n",
+            "11\n60.000\n",
+            "21\n50.000\n",
+            "31\n0.0\n",
+            "0\nENDSEC\n",
+            "0\nEOF\n",
+        );
+        let result = to_dxf(&test_segments(), 100, 100, &test_options());
+        let entities = &result[result.find("0\nSECTION\n2\nENTITIES").unwrap()..];
+
+        assert_eq!(entities, expected);
+    }
+
+    /*
+     * PDF and EPS
+     */
+    #[test]
+    fn test_to_pdf_xref_offsets() {
+        let result =
+            String::from_utf8(to_pdf(&test_segments(), 100, 100, &test_options())).unwrap();
+
+        let startxref = result.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let xref_offset: usize = result[startxref..].lines().next().unwrap().parse().unwrap();
+        assert!(result[xref_offset..].starts_with("xref\n0 5\n0000000000 65535 f \n"));
+
+        let entries: Vec<&str> = result[xref_offset..].lines().skip(3).take(4).collect();
+        for (i, entry) in entries.iter().enumerate() {
+            // Every entry is 20 bytes long including its newline
+            assert_eq!(entry.len() + 1, 20);
+
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(result[offset..].starts_with(&format!("{} 0 obj\n", i + 1)));
+        }
+    }
+
+    #[test]
+    fn test_to_pdf_stream_length() {
+        let result =
+            String::from_utf8(to_pdf(&test_segments(), 100, 100, &test_options())).unwrap();
+
+        let length_start = result.find("/Length ").unwrap() + "/Length ".len();
+        let length_end = length_start + result[length_start..].find(' ').unwrap();
+        let length: usize = result[length_start..length_end].parse().unwrap();
+        let stream_start = result.find("stream\n").unwrap() + "stream\n".len();
+        let stream_end = result.find("\nendstream").unwrap();
+
+        assert_eq!(stream_end - stream_start, length);
+    }
+
+    #[test]
+    fn test_to_eps_bounding_box() {
+        let options = ExportOptions {
+            page: Some(PageSize::A4),
+            ..test_options()
+        };
+        let result = to_eps(&test_segments(), 100, 100, &options);
+
+        assert!(result.contains("%%BoundingBox: 0 0 596 842\n"));
+        assert!(result.contains("%%HiResBoundingBox: 0 0 595.28 841.89\n"));
+    }
+}
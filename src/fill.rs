@@ -0,0 +1,229 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * fill.rs - Hatch, crosshatch, dot and gradient patterns filling closed turtle paths
+ * ========================================================================
+ */
+
+use crate::clip::clip_line;
+
+/**
+ * Length (pixels) of the short strokes that make up a dot pattern
+ */
+const DOT_LENGTH: f64 = 1.0;
+
+/**
+ * Represents how FILLED shapes are filled
+ *
+ * Hatches and dots use only strokes so that pen plotters can draw them. Gradients are solid fills that only SVG and
+ * PNG output can show
+ *
+ * Angles are in degrees clockwise from straight up, like headings, and spacings are the distance (pixels) between
+ * neighbouring lines or dots
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillPattern {
+    Hatch { angle: f64, spacing: f64 },
+    Crosshatch { angle: f64, spacing: f64 },
+    Dots { angle: f64, spacing: f64 },
+    Gradient(FillGradient),
+}
+
+/**
+ * Represents a smooth change between two pen colours (0-15) across a FILLED shape
+ *
+ * Linear gradients go from one side of the shape to the other in the direction of `angle` (degrees clockwise from
+ * straight up), and radial gradients go from the centre of the shape outwards
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillGradient {
+    Linear { angle: f64, from: i32, to: i32 },
+    Radial { from: i32, to: i32 },
+}
+
+impl FillGradient {
+    /**
+     * Look up a gradient by its (case-insensitive) kind, either linear or radial
+     */
+    pub fn from_name(name: &str, from: i32, to: i32, angle: f64) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "linear" => Some(FillGradient::Linear { angle, from, to }),
+            "radial" => Some(FillGradient::Radial { from, to }),
+            _ => None,
+        }
+    }
+
+    /**
+     * The pen colours (0-15) at the start and end of the gradient
+     */
+    pub fn colors(&self) -> (i32, i32) {
+        match *self {
+            FillGradient::Linear { from, to, .. } | FillGradient::Radial { from, to } => (from, to),
+        }
+    }
+}
+
+/**
+ * Represents a FILLED shape filled with a gradient, drawn by SVG and PNG output in between the turtle's segments
+ *
+ * Properties:
+ * polygon: Vec<(f64, f64)> - The outline of the shape, in canvas coordinates
+ * gradient: FillGradient - The gradient the shape is filled with
+ * segments: usize - The number of segments drawn before the FILLED block, so lines drawn inside it appear on top
+ * finished: usize - The number of segments drawn when the FILLED block finished, from when animations show the shape
+ * clip: Option<usize> - The clipping region the shape was drawn in, if any
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub polygon: Vec<(f64, f64)>,
+    pub gradient: FillGradient,
+    pub segments: usize,
+    pub finished: usize,
+    pub clip: Option<usize>,
+}
+
+impl Fill {
+    /**
+     * The points where a linear gradient starts and ends, on opposite sides of the shape
+     */
+    pub fn linear_ends(&self, angle: f64) -> ((f64, f64), (f64, f64)) {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let direction = (sin, -cos);
+        let (center_x, center_y) = self.center();
+
+        let (min, max) = extent(&self.polygon, direction);
+        let middle = center_x * direction.0 + center_y * direction.1;
+        let point = |distance: f64| {
+            (
+                center_x + direction.0 * (distance - middle),
+                center_y + direction.1 * (distance - middle),
+            )
+        };
+
+        (point(min), point(max))
+    }
+
+    /**
+     * The centre and radius of the circle a radial gradient spreads over, just reaching the furthest corner
+     */
+    pub fn radial_circle(&self) -> ((f64, f64), f64) {
+        let (center_x, center_y) = self.center();
+        let radius = self
+            .polygon
+            .iter()
+            .map(|&(x, y)| (x - center_x).hypot(y - center_y))
+            .fold(0.0, f64::max);
+
+        ((center_x, center_y), radius)
+    }
+
+    /**
+     * The centre of the shape's bounding box
+     */
+    fn center(&self) -> (f64, f64) {
+        let (min_x, max_x) = extent(&self.polygon, (1.0, 0.0));
+        let (min_y, max_y) = extent(&self.polygon, (0.0, 1.0));
+
+        ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0)
+    }
+}
+
+/**
+ * The smallest and largest distances of a polygon's corners measured in a direction
+ */
+fn extent(polygon: &[(f64, f64)], (dx, dy): (f64, f64)) -> (f64, f64) {
+    polygon
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(min, max), &(x, y)| {
+            let distance = x * dx + y * dy;
+            (min.min(distance), max.max(distance))
+        })
+}
+
+impl FillPattern {
+    /**
+     * Look up a pattern by its (case-insensitive) name
+     */
+    pub fn from_name(name: &str, angle: f64, spacing: f64) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "hatch" => Some(FillPattern::Hatch { angle, spacing }),
+            "crosshatch" => Some(FillPattern::Crosshatch { angle, spacing }),
+            "dots" => Some(FillPattern::Dots { angle, spacing }),
+            _ => None,
+        }
+    }
+
+    /**
+     * The strokes filling a polygon with the pattern, as (start, end) points, none for gradients
+     */
+    pub fn strokes(&self, polygon: &[(f64, f64)]) -> Vec<((f64, f64), (f64, f64))> {
+        match *self {
+            FillPattern::Hatch { angle, spacing } => hatch(polygon, angle, spacing, None),
+            FillPattern::Crosshatch { angle, spacing } => {
+                let mut strokes = hatch(polygon, angle, spacing, None);
+                strokes.extend(hatch(polygon, angle + 90.0, spacing, None));
+                strokes
+            }
+            FillPattern::Dots { angle, spacing } => hatch(polygon, angle, spacing, Some(spacing)),
+            FillPattern::Gradient(_) => Vec::new(),
+        }
+    }
+}
+
+/**
+ * Parallel lines across a polygon, cut to its inside, or evenly spaced dots along them if `dot_spacing` is given
+ */
+fn hatch(
+    polygon: &[(f64, f64)],
+    angle: f64,
+    spacing: f64,
+    dot_spacing: Option<f64>,
+) -> Vec<((f64, f64), (f64, f64))> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (along, across) = ((sin, -cos), (cos, sin));
+
+    // The extent of the polygon measured along and across the lines
+    let ((along_min, along_max), (across_min, across_max)) =
+        (extent(polygon, along), extent(polygon, across));
+
+    // Lines (and dots) sit on a grid aligned to the canvas, so neighbouring shapes' patterns line up
+    let mut strokes = Vec::new();
+    let mut offset = (across_min / spacing).ceil() * spacing;
+
+    while offset <= across_max {
+        let point = |distance: f64| {
+            (
+                across.0 * offset + along.0 * distance,
+                across.1 * offset + along.1 * distance,
+            )
+        };
+        let (start, end) = (point(along_min), point(along_max));
+
+        for (from, to) in clip_line(polygon, start, end) {
+            let (from, to) = (
+                along_min + (along_max - along_min) * from,
+                along_min + (along_max - along_min) * to,
+            );
+
+            match dot_spacing {
+                None => strokes.push((point(from), point(to))),
+                Some(dot_spacing) => {
+                    let mut distance = (from / dot_spacing).ceil() * dot_spacing;
+
+                    while distance <= to {
+                        strokes.push((
+                            point(distance - DOT_LENGTH / 2.0),
+                            point(distance + DOT_LENGTH / 2.0),
+                        ));
+                        distance += dot_spacing;
+                    }
+                }
+            }
+        }
+
+        offset += spacing;
+    }
+
+    strokes
+}
@@ -0,0 +1,122 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * lsystem.rs - Expansion and drawing of Lindenmayer systems (L-systems)
+ * ========================================================================
+ */
+
+use crate::constants::LSystemRule;
+use crate::turtle::{Turtle, TurtleState};
+use crate::utils::Rng;
+
+/**
+ * Maximum number of symbols an L-system may expand to, as the length grows exponentially with the iterations
+ */
+const MAX_LSYSTEM_LENGTH: usize = 1_000_000;
+
+/**
+ * Rewrite the axiom of an L-system with its rules for the given number of iterations
+ *
+ * Symbols without a rule are kept as they are. Symbols with several rules pick one at random in proportion to the
+ * rules' weights
+ *
+ * Arguments:
+ * axiom: &str - The starting symbols
+ * rules: &[LSystemRule] - The rewrite rules
+ * iterations: u32 - The number of times every symbol is rewritten
+ * rng: &mut Rng - The random number generator used to pick between rules
+ *
+ * Returns:
+ * Result<String, String> - The expanded symbols, or a description of the problem
+ */
+pub fn expand(
+    axiom: &str,
+    rules: &[LSystemRule],
+    iterations: u32,
+    rng: &mut Rng,
+) -> Result<String, String> {
+    let mut current = axiom.to_string();
+
+    for iteration in 1..=iterations {
+        let mut next = String::with_capacity(current.len());
+
+        for symbol in current.chars() {
+            let candidates: Vec<&LSystemRule> =
+                rules.iter().filter(|rule| rule.symbol == symbol).collect();
+            let total_weight: u64 = candidates.iter().map(|rule| rule.weight as u64).sum();
+
+            if total_weight == 0 {
+                next.push(symbol);
+                continue;
+            }
+
+            let mut choice = rng.below(total_weight);
+            for rule in candidates {
+                if choice < rule.weight as u64 {
+                    next.push_str(&rule.replacement);
+                    break;
+                }
+                choice -= rule.weight as u64;
+            }
+
+            if next.len() > MAX_LSYSTEM_LENGTH {
+                return Err(format!(
+                    "iteration {} expands to more than {} symbols",
+                    iteration, MAX_LSYSTEM_LENGTH
+                ));
+            }
+        }
+
+        current = next;
+    }
+
+    Ok(current)
+}
+
+/**
+ * Draw the symbols of an expanded L-system with the turtle
+ *
+ * F and G draw a line forward, f moves forward without drawing, + turns left and - turns right by the angle, | turns
+ * around, and [ and ] save and restore the turtle's state. Every other symbol is ignored
+ *
+ * The pen is lowered while drawing and put back afterwards. The brackets use their own stack, so they never touch
+ * states saved with PUSHSTATE
+ *
+ * Arguments:
+ * turtle: &mut Turtle - The turtle to draw with
+ * symbols: &str - The expanded symbols
+ * angle: f64 - The angle (degrees) of each turn
+ * distance: f64 - The distance (pixels) of each step forward
+ */
+pub fn draw(turtle: &mut Turtle, symbols: &str, angle: f64, distance: f64) {
+    let pen_down = turtle.is_pen_down();
+    let mut saved_states: Vec<TurtleState> = Vec::new();
+
+    turtle.pendown();
+    for symbol in symbols.chars() {
+        match symbol {
+            'F' | 'G' => turtle.forward(distance),
+            'f' => {
+                turtle.penup();
+                turtle.forward(distance);
+                turtle.pendown();
+            }
+            '+' => turtle.turn(-angle),
+            '-' => turtle.turn(angle),
+            '|' => turtle.turn(180.0),
+            '[' => saved_states.push(turtle.save_state()),
+            ']' => {
+                // The parser balances brackets, but a rule rewriting a bracket can still leave a stray ]
+                if let Some(state) = saved_states.pop() {
+                    turtle.restore_state(state);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if !pen_down {
+        turtle.penup();
+    }
+}
@@ -12,6 +12,42 @@
  *
  * To enable debug mode:
  *      DEBUG=1 cargo run <input_path> <output_path> <width> <height>
+ *
+ * The output format is chosen by the extension of <output_path>:
+ *      .svg, .png, .gcode, .hpgl, .dxf, .pdf, .eps, .html
+ *
+ * Plotter/CAD options (G-code, HPGL, DXF):
+ *      --feed-rate <mm/min> --travel-rate <mm/min>
+ *      --pen-up-z <mm> --pen-down-z <mm>
+ *      --plot-size <WIDTHxHEIGHT mm> --origin <top-left|bottom-left|center>
+ *      --optimize-paths
+ *
+ * Coordinate options:
+ *      --coords <screen|cartesian>
+ *
+ * Canvas options (SVG, PNG):
+ *      --fit --infinite --margin <pixels>
+ *      --background <0-15> --underlay <png_path>
+ *
+ * SVG options:
+ *      --compact-svg --precision <decimal places> --source-map
+ *
+ * Page options (PDF, EPS):
+ *      --page <a3|a4|a5|letter|legal>
+ *
+ * Program options:
+ *      --seed <number>
+ *
+ * Scaling (in the program):
+ *      SCALE <factor> [divisor], e.g. SCALE "1 "2 halves everything drawn after it
+ *
+ * Animation options (alongside the main output):
+ *      --animate <gif_or_svg_path> --frames <png_dir>
+ *      --frame-every <segments> --frame-delay <ms> --show-cursor --speed <px/s>
+ *
+ * Terminal options (alongside the main output):
+ *      --tty[=braille|block]
+ *      --stats[=text|json]
  * ========================================================================
  */
 
@@ -23,19 +59,34 @@ use clap::Parser;
 /*
  * Internal modules
  */
+mod animate;
+mod clip;
 mod constants;
 mod error;
 mod evaluator;
+mod export;
+mod fill;
+mod lsystem;
+mod optimize;
 mod parser;
+mod player;
+mod render;
+mod shape;
+mod stats;
+mod terminal;
 mod turtle;
 mod utils;
 
 /*
  * Internal imports
  */
+use animate::AnimationOptions;
 use evaluator::evaluate_program;
+use export::{parse_plot_size, ExportOptions, Origin, PageSize};
 use parser::parse_program;
-use turtle::Turtle;
+use stats::StatsFormat;
+use terminal::TtyMode;
+use turtle::{CoordinateSystem, Turtle};
 use utils::read_file;
 
 /**
@@ -47,6 +98,122 @@ struct Args {
     image_path: std::path::PathBuf,
     width: u32,
     height: u32,
+
+    /// Speed (mm/min) at which lines are drawn in G-code output
+    #[arg(long, default_value_t = 1500.0)]
+    feed_rate: f64,
+
+    /// Speed (mm/min) at which the lifted pen travels in G-code output
+    #[arg(long, default_value_t = 3000.0)]
+    travel_rate: f64,
+
+    /// Z height (mm) of the lifted pen in G-code output
+    #[arg(long, default_value_t = 5.0, allow_negative_numbers = true)]
+    pen_up_z: f64,
+
+    /// Z height (mm) of the lowered pen in G-code output
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pen_down_z: f64,
+
+    /// Physical area (WIDTHxHEIGHT in mm) the drawing is scaled to fit in plotter/CAD output
+    #[arg(long, value_parser = parse_plot_size)]
+    plot_size: Option<(f64, f64)>,
+
+    /// Where the machine origin lies on the drawing in plotter/CAD output
+    #[arg(long, value_enum, default_value_t = Origin::BottomLeft)]
+    origin: Origin,
+
+    /// Reorder lines to reduce pen-up travel in G-code, HPGL, DXF, PDF and EPS output (PDF/EPS keep colours in order)
+    #[arg(long)]
+    optimize_paths: bool,
+
+    /// Coordinate system used by XCOR, YCOR, SETX and SETY (can be changed with SETCOORDS)
+    #[arg(long, value_enum, default_value_t = CoordinateSystem::Screen)]
+    coords: CoordinateSystem,
+
+    /// Zoom SVG/PNG output to the bounding box of the drawing, keeping the output size
+    #[arg(long, conflicts_with = "infinite")]
+    fit: bool,
+
+    /// Size SVG/PNG output to the bounding box of the drawing (width and height only set the starting position)
+    #[arg(long)]
+    infinite: bool,
+
+    /// Space (pixels) left around the drawing with --fit or --infinite
+    #[arg(long, default_value_t = 10.0)]
+    margin: f64,
+
+    /// Color (0-15) of the canvas background (can be changed with SETBACKGROUND)
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(i32).range(0..=15))]
+    background: i32,
+
+    /// PNG image drawn underneath SVG/PNG output, stretched over the canvas (e.g. for tracing)
+    #[arg(long, value_name = "PATH")]
+    underlay: Option<std::path::PathBuf>,
+
+    /// Join connected lines in SVG output into polylines grouped by colour, for much smaller files
+    #[arg(long)]
+    compact_svg: bool,
+
+    /// Number of decimal places coordinates of lines are written with in SVG output
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(0..=10))]
+    precision: u8,
+
+    /// Tag SVG elements with the source line and procedure that drew them, grouping each top-level procedure call
+    #[arg(long)]
+    source_map: bool,
+
+    /// Paper size for PDF/EPS output (defaults to the size of the canvas)
+    #[arg(long, value_enum)]
+    page: Option<PageSize>,
+
+    /// Seed of the random number generator (e.g. for stochastic L-system rules)
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Also write an animated GIF (.gif) or self-animating SVG (.svg) of the drawing process
+    #[arg(long, value_name = "PATH")]
+    animate: Option<std::path::PathBuf>,
+
+    /// Also write the frames of the drawing process as numbered PNGs into a directory
+    #[arg(long, value_name = "DIR")]
+    frames: Option<std::path::PathBuf>,
+
+    /// Capture an animation frame every N lines drawn (in addition to FRAME/WAIT statements)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    frame_every: Option<u64>,
+
+    /// Time (ms) each animation frame is shown for, unless set by a WAIT statement
+    #[arg(long, default_value_t = 100)]
+    frame_delay: u32,
+
+    /// Draw the turtle cursor on each animation frame
+    #[arg(long)]
+    show_cursor: bool,
+
+    /// Speed (pixels per second) at which lines are drawn in a self-animating SVG
+    #[arg(long, default_value_t = 200.0)]
+    speed: f64,
+
+    /// Also print the drawing to the terminal, using braille or half block characters
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "braille"
+    )]
+    tty: Option<TtyMode>,
+
+    /// Also print statistics about the drawing (e.g. ink length, pen-up travel) and the program run
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    stats: Option<StatsFormat>,
 }
 
 fn main() -> Result<(), String> {
@@ -59,11 +226,43 @@ fn main() -> Result<(), String> {
 
     let content = read_file(&file_path);
 
-    let turtle = Turtle::new(width, height, image_path);
+    let export_options = ExportOptions {
+        feed_rate: args.feed_rate,
+        travel_rate: args.travel_rate,
+        pen_up_z: args.pen_up_z,
+        pen_down_z: args.pen_down_z,
+        plot_size: args.plot_size,
+        origin: args.origin,
+        optimize_paths: args.optimize_paths,
+        page: args.page,
+        tty: args.tty,
+        stats: args.stats,
+        fit: args.fit,
+        infinite: args.infinite,
+        margin: args.margin,
+        background: args.background,
+        underlay: args.underlay,
+        compact_svg: args.compact_svg,
+        precision: args.precision as usize,
+        source_map: args.source_map,
+    };
+
+    let animation_options = AnimationOptions {
+        animation_path: args.animate,
+        frames_dir: args.frames,
+        frame_every: args.frame_every.map(|n| n as usize),
+        frame_delay: args.frame_delay,
+        show_cursor: args.show_cursor,
+        speed: args.speed,
+    };
+
+    let mut turtle = Turtle::new(width, height, image_path, export_options, animation_options);
+    turtle.set_source(content.clone());
+    turtle.set_coordinate_system(args.coords);
 
     let ast = parse_program(content);
 
-    evaluate_program(turtle, ast);
+    evaluate_program(turtle, ast, args.seed);
 
     Ok(()) // Exit successfully
 }
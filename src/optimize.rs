@@ -0,0 +1,637 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * optimize.rs - Reordering of drawn lines to reduce pen-up travel on plotters
+ * ========================================================================
+ */
+
+use crate::turtle::Segment;
+
+/**
+ * Largest number of strokes in a single colour that is improved with 2-opt after the nearest-neighbour ordering,
+ * since every pass compares every pair of strokes
+ */
+const TWO_OPT_LIMIT: usize = 2000;
+
+/**
+ * Largest number of 2-opt passes over the strokes of a single colour
+ */
+const TWO_OPT_PASSES: usize = 8;
+
+/**
+ * Represents a run of connected lines drawn in one go, which can be drawn from either end
+ *
+ * Properties:
+ * segments: Vec<Segment> - The lines of the stroke, each starting where the previous one ended
+ * reversed: bool - Whether the stroke is drawn from its last point back to its first
+ */
+#[derive(Debug, Clone, PartialEq)]
+struct Stroke {
+    segments: Vec<Segment>,
+    reversed: bool,
+}
+
+impl Stroke {
+    fn first(&self) -> (f64, f64) {
+        let first = &self.segments[0];
+        (first.x1, first.y1)
+    }
+
+    fn last(&self) -> (f64, f64) {
+        let last = &self.segments[self.segments.len() - 1];
+        (last.x2, last.y2)
+    }
+
+    /**
+     * The point the pen is lowered at to draw the stroke
+     */
+    fn start(&self) -> (f64, f64) {
+        if self.reversed {
+            self.last()
+        } else {
+            self.first()
+        }
+    }
+
+    /**
+     * The point the pen is lifted at after drawing the stroke
+     */
+    fn end(&self) -> (f64, f64) {
+        if self.reversed {
+            self.first()
+        } else {
+            self.last()
+        }
+    }
+
+    /**
+     * The lines of the stroke in the order and direction they are drawn
+     */
+    fn into_segments(self) -> Vec<Segment> {
+        if self.reversed {
+            self.segments.iter().rev().map(reversed).collect()
+        } else {
+            self.segments
+        }
+    }
+}
+
+fn distance((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+    (x2 - x1).hypot(y2 - y1)
+}
+
+/**
+ * The total distance the pen travels while lifted between the given lines, drawn in order
+ */
+fn travel_length(lines: &[Segment]) -> f64 {
+    lines
+        .windows(2)
+        .map(|pair| distance((pair[0].x2, pair[0].y2), (pair[1].x1, pair[1].y1)))
+        .sum()
+}
+
+/**
+ * The same line or curve, drawn from its end back to its start
+ */
+fn reversed(segment: &Segment) -> Segment {
+    Segment {
+        x1: segment.x2,
+        y1: segment.y2,
+        x2: segment.x1,
+        y2: segment.y1,
+        controls: segment
+            .controls
+            .map(|(cx1, cy1, cx2, cy2)| (cx2, cy2, cx1, cy1)),
+        ..segment.clone()
+    }
+}
+
+/**
+ * Whether a straight line carries straight on from another in the same direction, so the two can be drawn as one
+ */
+fn continues(previous: &Segment, next: &Segment) -> bool {
+    if previous.controls.is_some() || next.controls.is_some() {
+        return false;
+    }
+
+    let (dx1, dy1) = (previous.x2 - previous.x1, previous.y2 - previous.y1);
+    let (dx2, dy2) = (next.x2 - next.x1, next.y2 - next.y1);
+    let cross = dx1 * dy2 - dy1 * dx2;
+    let dot = dx1 * dx2 + dy1 * dy2;
+
+    cross.abs() <= 1e-9 * dx1.hypot(dy1) * dx2.hypot(dy2) && dot > 0.0
+}
+
+/**
+ * Join lines of a single colour into strokes, merging lines that carry straight on from each other
+ */
+fn strokes(segments: Vec<Segment>) -> Vec<Stroke> {
+    let mut strokes: Vec<Stroke> = Vec::new();
+
+    for segment in segments {
+        // Lines that go nowhere leave no mark on a plotter
+        if segment.x1 == segment.x2 && segment.y1 == segment.y2 && segment.controls.is_none() {
+            continue;
+        }
+
+        match strokes.last_mut() {
+            Some(stroke) if stroke.last() == (segment.x1, segment.y1) => {
+                let previous = stroke.segments.last_mut().unwrap();
+
+                if continues(previous, &segment) {
+                    previous.x2 = segment.x2;
+                    previous.y2 = segment.y2;
+                } else {
+                    stroke.segments.push(segment);
+                }
+            }
+            _ => strokes.push(Stroke {
+                segments: vec![segment],
+                reversed: false,
+            }),
+        }
+    }
+
+    strokes
+}
+
+/**
+ * Represents a uniform grid over the ends of strokes, for finding the stroke nearest the pen without measuring the
+ * distance to every stroke
+ *
+ * Properties:
+ * origin: (f64, f64) - The smallest x and y-coordinates of any stroke end
+ * cell_size: f64 - The width and height of each cell
+ * columns: usize - The number of cells across the grid
+ * rows: usize - The number of cells down the grid
+ * cells: Vec<Vec<(usize, bool)>> - The stroke (by index) and end (whether it is the last point) in each cell
+ */
+struct EndGrid {
+    origin: (f64, f64),
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<(usize, bool)>>,
+}
+
+impl EndGrid {
+    fn new(strokes: &[Stroke]) -> Self {
+        let ends: Vec<(f64, f64)> = strokes
+            .iter()
+            .flat_map(|stroke| [stroke.first(), stroke.last()])
+            .collect();
+
+        let min_x = ends.iter().map(|end| end.0).fold(f64::MAX, f64::min);
+        let min_y = ends.iter().map(|end| end.1).fold(f64::MAX, f64::min);
+        let max_x = ends.iter().map(|end| end.0).fold(f64::MIN, f64::max);
+        let max_y = ends.iter().map(|end| end.1).fold(f64::MIN, f64::max);
+
+        // Roughly one stroke per cell, so each search only measures the strokes around the pen, and never more cells
+        // along a side than there are strokes (e.g. for a long, thin drawing)
+        let (width, height) = ((max_x - min_x).max(1.0), (max_y - min_y).max(1.0));
+        let cell_size = (width * height / strokes.len() as f64)
+            .sqrt()
+            .max(width.max(height) / strokes.len() as f64);
+        let columns = ((max_x - min_x) / cell_size) as usize + 1;
+        let rows = ((max_y - min_y) / cell_size) as usize + 1;
+
+        let mut grid = EndGrid {
+            origin: (min_x, min_y),
+            cell_size,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+        };
+        for (i, &end) in ends.iter().enumerate() {
+            let (column, row) = grid.cell(end);
+            grid.cells[row * columns + column].push((i / 2, i % 2 == 1));
+        }
+
+        grid
+    }
+
+    /**
+     * The cell containing a point, or the nearest cell to it if it is outside the grid
+     */
+    fn cell(&self, (x, y): (f64, f64)) -> (usize, usize) {
+        let column = ((x - self.origin.0) / self.cell_size).max(0.0) as usize;
+        let row = ((y - self.origin.1) / self.cell_size).max(0.0) as usize;
+
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+
+    /**
+     * The stroke not yet drawn with an end nearest the given position, and whether that end is its last point
+     *
+     * Cells are searched in growing rings around the position, until no unsearched cell can hold a nearer end. Ends
+     * of strokes that have been drawn are dropped from the grid as they are found
+     */
+    fn nearest(
+        &mut self,
+        strokes: &[Stroke],
+        drawn: &[bool],
+        position: (f64, f64),
+    ) -> Option<(usize, bool)> {
+        let (column, row) = self.cell(position);
+        let mut best: Option<(f64, usize, bool)> = None;
+
+        for ring in 0..self.columns.max(self.rows) {
+            for y in row.saturating_sub(ring)..=(row + ring).min(self.rows - 1) {
+                // Only the cells on the border of the ring are new
+                let xs: Vec<usize> = if y.abs_diff(row) == ring {
+                    (column.saturating_sub(ring)..=(column + ring).min(self.columns - 1)).collect()
+                } else {
+                    [column.checked_sub(ring), Some(column + ring)]
+                        .into_iter()
+                        .flatten()
+                        .filter(|&x| x < self.columns)
+                        .collect()
+                };
+
+                for x in xs {
+                    let cell = &mut self.cells[y * self.columns + x];
+                    cell.retain(|&(i, _)| !drawn[i]);
+
+                    for &(i, last) in cell.iter() {
+                        let end = if last {
+                            strokes[i].last()
+                        } else {
+                            strokes[i].first()
+                        };
+                        let candidate = (distance(position, end), i, last);
+
+                        // Ties go to the earliest stroke, drawn forwards, so the order is deterministic
+                        if best.is_none_or(|best| candidate < best) {
+                            best = Some(candidate);
+                        }
+                    }
+                }
+            }
+
+            // Every end in a further ring is at least this far from the position
+            if best.is_some_and(|(travel, _, _)| travel <= ring as f64 * self.cell_size) {
+                break;
+            }
+        }
+
+        best.map(|(_, i, last)| (i, last))
+    }
+}
+
+/**
+ * Order strokes by repeatedly drawing whichever stroke (from either end) is closest to where the pen is
+ */
+fn nearest_neighbour(strokes: Vec<Stroke>, position: Option<(f64, f64)>) -> Vec<Stroke> {
+    if strokes.is_empty() {
+        return strokes;
+    }
+
+    let mut grid = EndGrid::new(&strokes);
+    let mut drawn = vec![false; strokes.len()];
+    let mut order = Vec::with_capacity(strokes.len());
+
+    // Without a previous position, the first stroke is drawn as it was
+    let mut next = match position {
+        Some(position) => grid.nearest(&strokes, &drawn, position),
+        None => Some((0, false)),
+    };
+    while let Some((i, reversed)) = next {
+        drawn[i] = true;
+        order.push((i, reversed));
+
+        let end = if reversed {
+            strokes[i].first()
+        } else {
+            strokes[i].last()
+        };
+        next = grid.nearest(&strokes, &drawn, end);
+    }
+
+    let mut strokes: Vec<Option<Stroke>> = strokes.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|(i, reversed)| {
+            let mut stroke = strokes[i].take()?;
+            stroke.reversed = reversed;
+            Some(stroke)
+        })
+        .collect()
+}
+
+/**
+ * Improve an ordering of strokes by reversing any run of them (drawing it backwards) that shortens the travel
+ */
+fn two_opt(strokes: &mut [Stroke], position: Option<(f64, f64)>) {
+    let travel_to =
+        |from: Option<(f64, f64)>, to: (f64, f64)| from.map_or(0.0, |from| distance(from, to));
+
+    for _ in 0..TWO_OPT_PASSES {
+        let mut improved = false;
+
+        for i in 0..strokes.len() {
+            for j in i + 1..strokes.len() {
+                let before = if i == 0 {
+                    position
+                } else {
+                    Some(strokes[i - 1].end())
+                };
+                let after = strokes.get(j + 1).map(Stroke::start);
+
+                let current = travel_to(before, strokes[i].start())
+                    + after.map_or(0.0, |after| distance(strokes[j].end(), after));
+                let swapped = travel_to(before, strokes[j].end())
+                    + after.map_or(0.0, |after| distance(strokes[i].start(), after));
+
+                if swapped < current - 1e-9 {
+                    strokes[i..=j].reverse();
+                    for stroke in &mut strokes[i..=j] {
+                        stroke.reversed = !stroke.reversed;
+                    }
+                    improved = true;
+                }
+            }
+        }
+
+        if !improved {
+            break;
+        }
+    }
+}
+
+/**
+ * Reorder a run of painted lines to reduce pen-up travel, either drawing each colour in turn or only reordering
+ * lines within each stretch of a single colour
+ */
+fn optimize_run(
+    run: Vec<Segment>,
+    by_color: bool,
+    position: &mut Option<(f64, f64)>,
+    output: &mut Vec<Segment>,
+) {
+    // Colours are drawn in the order they first appear, so pens are changed as rarely as possible
+    let mut colors: Vec<Vec<Segment>> = Vec::new();
+    for segment in run {
+        let same_color = |group: &&mut Vec<Segment>| {
+            (group[0].color, group[0].shade) == (segment.color, segment.shade)
+        };
+        let group = if by_color {
+            colors.iter_mut().find(same_color)
+        } else {
+            colors.last_mut().filter(same_color)
+        };
+
+        match group {
+            Some(group) => group.push(segment),
+            None => colors.push(vec![segment]),
+        }
+    }
+
+    for segments in colors {
+        let mut ordered = nearest_neighbour(strokes(segments), *position);
+        if ordered.len() <= TWO_OPT_LIMIT {
+            two_opt(&mut ordered, *position);
+        }
+
+        if let Some(last) = ordered.last() {
+            *position = Some(last.end());
+        }
+        output.extend(ordered.into_iter().flat_map(Stroke::into_segments));
+    }
+}
+
+/**
+ * Reorder the turtle's lines so a plotter spends less time travelling with the pen up, without changing the drawing
+ *
+ * Connected lines are joined into strokes and lines that carry straight on are merged. Strokes are then grouped by
+ * colour and ordered (and drawn forwards or backwards) with a nearest-neighbour search improved by 2-opt. Erasing
+ * and reversing lines depend on what was drawn before them, so they stay in place and nothing is moved past them.
+ * Pen-up movements are dropped, as plotters travel between strokes by themselves. If the new order would travel
+ * further than the original one (which the heuristics cannot rule out), the lines are kept in their original order
+ *
+ * Plotters change pens between colours, so it pays to draw all lines of a colour together. On a page, where lines
+ * of different colours overlap, that would change which one is on top, so only lines within each stretch of a
+ * single colour are reordered
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * by_color: bool - Whether all lines of each colour are drawn together, rather than keeping colours in order
+ *
+ * Returns:
+ * Vec<Segment> - The painted lines in their new order
+ */
+pub fn optimize_paths(segments: &[Segment], by_color: bool) -> Vec<Segment> {
+    let mut output = Vec::with_capacity(segments.len());
+    let mut position: Option<(f64, f64)> = None;
+    let mut run: Vec<Segment> = Vec::new();
+
+    for segment in segments.iter().filter(|segment| segment.pen_down) {
+        if segment.is_painted() {
+            run.push(segment.clone());
+        } else {
+            optimize_run(
+                std::mem::take(&mut run),
+                by_color,
+                &mut position,
+                &mut output,
+            );
+
+            position = Some((segment.x2, segment.y2));
+            output.push(segment.clone());
+        }
+    }
+    optimize_run(run, by_color, &mut position, &mut output);
+
+    let original: Vec<Segment> = segments
+        .iter()
+        .filter(|segment| segment.pen_down)
+        .cloned()
+        .collect();
+    if travel_length(&output) > travel_length(&original) {
+        return original;
+    }
+
+    output
+}
+
+/*
+ * Unit tests
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::turtle::PenMode;
+
+    /**
+     * A turtle-like walk of lines, each starting where the last ended, in a mix of colours with some drawn with the
+     * pen up, from a simple deterministic pseudo-random sequence
+     */
+    fn random_walk(seed: u64, length: usize) -> Vec<Segment> {
+        let mut state = seed;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as f64 / (1u64 << 31) as f64
+        };
+
+        let mut position = (next() * 500.0, next() * 500.0);
+        (0..length)
+            .map(|_| {
+                let end = (next() * 500.0, next() * 500.0);
+                let pen_down = next() < 0.6;
+                let color = (next() * 3.0) as i32;
+                let segment =
+                    Segment::test_line(position.0, position.1, end.0, end.1, pen_down, color);
+
+                position = end;
+                segment
+            })
+            .collect()
+    }
+
+    /**
+     * The painted lines, each with its ends in a fixed order, sorted so drawings can be compared regardless of the
+     * order and direction of their lines
+     */
+    fn painted_lines(segments: &[Segment]) -> Vec<(i32, [f64; 4])> {
+        let mut lines: Vec<_> = segments
+            .iter()
+            .filter(|segment| segment.pen_down)
+            .map(|segment| {
+                let (start, end) = ((segment.x1, segment.y1), (segment.x2, segment.y2));
+                let ((x1, y1), (x2, y2)) = if start <= end {
+                    (start, end)
+                } else {
+                    (end, start)
+                };
+                (segment.color, [x1, y1, x2, y2])
+            })
+            .collect();
+        lines.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        lines
+    }
+
+    /*
+     * Painted lines
+     */
+    #[test]
+    fn test_optimize_paths_keeps_painted_lines() {
+        for seed in 0..20 {
+            let segments = random_walk(seed, 300);
+
+            for by_color in [true, false] {
+                let result = optimize_paths(&segments, by_color);
+
+                assert_eq!(painted_lines(&result), painted_lines(&segments));
+                assert!(result.iter().all(|segment| segment.pen_down));
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimize_paths_keeps_colour_order_on_pages() {
+        let segments = random_walk(7, 300);
+        let colors = |segments: &[Segment]| {
+            let mut colors: Vec<i32> = segments
+                .iter()
+                .filter(|segment| segment.pen_down)
+                .map(|segment| segment.color)
+                .collect();
+            colors.dedup();
+            colors
+        };
+
+        let result = optimize_paths(&segments, false);
+
+        assert_eq!(colors(&result), colors(&segments));
+    }
+
+    /*
+     * Travel
+     */
+    #[test]
+    fn test_optimize_paths_never_increases_travel() {
+        for seed in 0..20 {
+            for length in [1, 2, 5, 50, 300] {
+                let segments = random_walk(seed, length);
+                let drawn: Vec<Segment> = segments
+                    .iter()
+                    .filter(|segment| segment.pen_down)
+                    .cloned()
+                    .collect();
+
+                for by_color in [true, false] {
+                    let result = optimize_paths(&segments, by_color);
+
+                    assert!(travel_length(&result) <= travel_length(&drawn));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_optimize_paths_reduces_travel() {
+        let segments = random_walk(3, 300);
+        let drawn: Vec<Segment> = segments
+            .iter()
+            .filter(|segment| segment.pen_down)
+            .cloned()
+            .collect();
+
+        let result = optimize_paths(&segments, true);
+
+        assert!(travel_length(&result) < travel_length(&drawn) / 2.0);
+    }
+
+    /*
+     * Erasing and reversing
+     */
+    #[test]
+    fn test_optimize_paths_keeps_erasing_and_reversing_in_place() {
+        let with_mode = |segment: Segment, mode: PenMode| Segment { mode, ..segment };
+        let segments = vec![
+            Segment::test_line(0.0, 0.0, 100.0, 0.0, true, 1),
+            Segment::test_line(300.0, 300.0, 310.0, 310.0, true, 1),
+            with_mode(
+                Segment::test_line(0.0, 0.0, 100.0, 0.0, true, 1),
+                PenMode::Erase,
+            ),
+            Segment::test_line(50.0, -50.0, 50.0, 50.0, true, 2),
+            Segment::test_line(400.0, 0.0, 410.0, 0.0, true, 1),
+            with_mode(
+                Segment::test_line(0.0, 0.0, 100.0, 0.0, true, 1),
+                PenMode::Reverse,
+            ),
+            Segment::test_line(0.0, 10.0, 100.0, 10.0, true, 1),
+        ];
+
+        let result = optimize_paths(&segments, true);
+
+        // Every line stays on the same side of each erasing or reversing line it was drawn before or after
+        let position = |segment: &Segment| {
+            result
+                .iter()
+                .position(|s| s == segment || *s == reversed(segment))
+                .unwrap()
+        };
+        for (i, barrier) in segments.iter().enumerate() {
+            if barrier.is_painted() {
+                continue;
+            }
+
+            for (j, segment) in segments.iter().enumerate() {
+                if i != j {
+                    assert_eq!(
+                        position(segment) < position(barrier),
+                        j < i,
+                        "{:?} moved past {:?}",
+                        segment,
+                        barrier
+                    );
+                }
+            }
+        }
+    }
+}
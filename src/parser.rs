@@ -12,9 +12,12 @@
 use nom::{
     branch::alt,
     bytes::complete::{tag, tag_no_case, take_until, take_while1},
-    character::complete::{char, digit1, line_ending, multispace0, multispace1, not_line_ending},
-    combinator::{map, opt, peek},
-    multi::many0,
+    character::complete::{
+        anychar, char, digit1, line_ending, multispace0, multispace1, not_line_ending, space1,
+    },
+    combinator::{cut, map, opt, peek, verify},
+    error::context,
+    multi::{count, many0},
     sequence::{delimited, preceded, terminated, tuple},
     IResult,
 };
@@ -22,9 +25,11 @@ use nom::{
 /*
  * Internal imports
  */
-use crate::constants::{Block, Expression, Identifier, Statement};
+use crate::constants::{Block, Expression, Identifier, LSystemRule, Statement};
 use crate::error::{debug, print_error};
 
+use std::cell::Cell;
+
 /*
  * Type alias for verbose parsing error for more detailed error messages
  */
@@ -46,7 +51,20 @@ pub fn parse_program(content: String) -> Block {
     debug("initial parser input", &format!("{:#?}", input));
 
     match parse_all(input) {
-        Ok((_, ast)) => ast,
+        Ok((_, mut ast)) => {
+            let line_starts: Vec<usize> = std::iter::once(0)
+                .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+                .collect();
+
+            // Markers hold the length of the input remaining after them, so turn that into a line
+            // (numbered from 1, as in text editors)
+            for_each_source_line(&mut ast, &mut |marker| {
+                let offset = input.len().saturating_sub(*marker);
+                *marker = line_starts.partition_point(|&line_start| line_start <= offset);
+            });
+
+            ast
+        }
         Err(error) => {
             print_error(
                 "syntax error",
@@ -67,10 +85,10 @@ pub fn parse_program(content: String) -> Block {
  *
  * Combines all component parsers into a single parser
  */
-fn parse_all(input: &str) -> IResult<&str, Block, ParserError> {
+fn parse_all(input: &str) -> IResult<&str, Block, ParserError<'_>> {
     many0(preceded(
         multispace0,
-        alt((map(parse_comment, |_| None), map(parse_statement, Some))),
+        alt((map(parse_comment, |_| Vec::new()), parse_located_statement)),
     ))(input)
     .map(|(remaining, statements)| {
         let filtered = statements.into_iter().flatten().collect();
@@ -82,25 +100,76 @@ fn parse_all(input: &str) -> IResult<&str, Block, ParserError> {
 /*
  * Blocks
  */
-fn parse_block(input: &str) -> IResult<&str, Block, ParserError> {
+fn parse_block(input: &str) -> IResult<&str, Block, ParserError<'_>> {
+    delimited(
+        tag("["),
+        many0(preceded(multispace0, parse_located_statement)),
+        preceded(multispace0, tag("]")),
+    )(input)
+    .map(|(remaining, statements)| (remaining, statements.into_iter().flatten().collect()))
+}
+
+/**
+ * A helper function for parsing a bracketed list of expressions, e.g. the points of a spline
+ */
+fn parse_expression_list(input: &str) -> IResult<&str, Vec<Expression>, ParserError<'_>> {
     delimited(
         tag("["),
-        many0(preceded(multispace0, parse_statement)),
+        many0(preceded(multispace0, parse_expression)),
         preceded(multispace0, tag("]")),
     )(input)
 }
 
+/*
+ * Source lines
+ */
+
+/**
+ * A helper function for parsing a statement preceded by a marker of where it starts
+ *
+ * While parsing, the marker holds the length of the input remaining from the start of the statement,
+ * which `parse_program` later turns into a line number as only it knows the whole program
+ */
+fn parse_located_statement(input: &str) -> IResult<&str, Vec<Statement>, ParserError<'_>> {
+    let remaining = input.len();
+    let (input, statement) = parse_statement(input)?;
+
+    Ok((input, vec![Statement::SourceLine(remaining), statement]))
+}
+
+/**
+ * Apply a function to every source line marker in a block, including those in nested blocks
+ *
+ * Arguments:
+ * block: &mut Block - The block of statements to walk through
+ * f: &mut impl FnMut(&mut usize) - The function to apply to the value of each marker
+ */
+fn for_each_source_line(block: &mut Block, f: &mut impl FnMut(&mut usize)) {
+    for statement in block.iter_mut() {
+        match statement {
+            Statement::SourceLine(marker) => f(marker),
+            Statement::If(_, body)
+            | Statement::While(_, body)
+            | Statement::Repeat(_, body)
+            | Statement::WithTransform(body)
+            | Statement::Filled(body) => for_each_source_line(body, f),
+            Statement::ProcedureDefinition { body, .. } => for_each_source_line(body, f),
+            _ => {}
+        }
+    }
+}
+
 /*
  * Arguments
  */
-fn parse_arguments(input: &str) -> IResult<&str, Vec<Expression>, ParserError> {
+fn parse_arguments(input: &str) -> IResult<&str, Vec<Expression>, ParserError<'_>> {
     many0(preceded(multispace1, parse_expression))(input)
 }
 
 /*
  * Comments
  */
-fn parse_comment(input: &str) -> IResult<&str, (), ParserError> {
+fn parse_comment(input: &str) -> IResult<&str, (), ParserError<'_>> {
     preceded(
         tag("//"),
         map(terminated(not_line_ending, line_ending), |_| ()),
@@ -110,7 +179,7 @@ fn parse_comment(input: &str) -> IResult<&str, (), ParserError> {
 /*
  * Identifiers
  */
-fn parse_identifier(input: &str) -> IResult<&str, Identifier, ParserError> {
+fn parse_identifier(input: &str) -> IResult<&str, Identifier, ParserError<'_>> {
     let (input, prefix) = opt(alt((tag("\""), tag(":"))))(input)?;
     let (input, name) = take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)?;
 
@@ -119,7 +188,7 @@ fn parse_identifier(input: &str) -> IResult<&str, Identifier, ParserError> {
         None => {
             let name_str: &str = &(name.to_lowercase());
             match name_str {
-                "xcor" | "ycor" | "heading" | "color" => "Q",
+                "xcor" | "ycor" | "heading" | "color" | "penmode" => "Q",
                 _ => "",
             }
         }
@@ -134,7 +203,7 @@ fn parse_identifier(input: &str) -> IResult<&str, Identifier, ParserError> {
 /*
  * Terminal values
  */
-fn parse_integer(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_integer(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("\"")(input)?;
     let (input, sign) = opt(char('-'))(input)?;
     let (input, digits) = digit1(input)?;
@@ -158,14 +227,14 @@ fn parse_integer(input: &str) -> IResult<&str, Expression, ParserError> {
     }
 }
 
-fn parse_string(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_string(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("\"")(input)?;
     let (input, content) = take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)?;
 
     Ok((input, Expression::StringLiteral(content.to_string())))
 }
 
-fn parse_variable(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_variable(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag(":")(input)?;
     let (input, name) = take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)?;
 
@@ -175,38 +244,50 @@ fn parse_variable(input: &str) -> IResult<&str, Expression, ParserError> {
 /*
  * Queries
  */
-fn parse_xcor(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_xcor(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag_no_case("xcor")(input)?;
 
     Ok((input, Expression::QueryXCor))
 }
 
-fn parse_ycor(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_ycor(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag_no_case("ycor")(input)?;
 
     Ok((input, Expression::QueryYCor))
 }
 
-fn parse_heading(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_heading(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag_no_case("heading")(input)?;
 
     Ok((input, Expression::QueryHeading))
 }
 
-fn parse_color(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_color(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag_no_case("color")(input)?;
 
     Ok((input, Expression::QueryColor))
 }
 
-fn parse_queries(input: &str) -> IResult<&str, Expression, ParserError> {
-    alt((parse_xcor, parse_ycor, parse_heading, parse_color))(input)
+fn parse_penmode(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
+    let (input, _) = tag_no_case("penmode")(input)?;
+
+    Ok((input, Expression::QueryPenMode))
+}
+
+fn parse_queries(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
+    alt((
+        parse_xcor,
+        parse_ycor,
+        parse_heading,
+        parse_color,
+        parse_penmode,
+    ))(input)
 }
 
 /*
  * Expressions
  */
-fn parse_value(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_value(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     alt((
         parse_parentheses,
         parse_queries,
@@ -216,14 +297,14 @@ fn parse_value(input: &str) -> IResult<&str, Expression, ParserError> {
     ))(input)
 }
 
-fn parse_parentheses(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_parentheses(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     delimited(tag("("), parse_expression, tag(")"))(input)
 }
 
 /*
  * Binary operations
  */
-fn parse_binary_ops(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_binary_ops(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     alt((
         parse_addition,
         parse_subtraction,
@@ -239,7 +320,7 @@ fn parse_binary_ops(input: &str) -> IResult<&str, Expression, ParserError> {
     ))(input)
 }
 
-fn parse_addition(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_addition(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("+")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -248,7 +329,7 @@ fn parse_addition(input: &str) -> IResult<&str, Expression, ParserError> {
     Ok((input, Expression::Addition(Box::new(left), Box::new(right))))
 }
 
-fn parse_subtraction(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_subtraction(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("-")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -260,7 +341,7 @@ fn parse_subtraction(input: &str) -> IResult<&str, Expression, ParserError> {
     ))
 }
 
-fn parse_multiplication(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_multiplication(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("*")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -272,7 +353,7 @@ fn parse_multiplication(input: &str) -> IResult<&str, Expression, ParserError> {
     ))
 }
 
-fn parse_division(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_division(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("/")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -281,7 +362,7 @@ fn parse_division(input: &str) -> IResult<&str, Expression, ParserError> {
     Ok((input, Expression::Division(Box::new(left), Box::new(right))))
 }
 
-fn parse_modulo(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_modulo(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("%")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -290,7 +371,7 @@ fn parse_modulo(input: &str) -> IResult<&str, Expression, ParserError> {
     Ok((input, Expression::Modulo(Box::new(left), Box::new(right))))
 }
 
-fn parse_equals(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_equals(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("EQ")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -299,7 +380,7 @@ fn parse_equals(input: &str) -> IResult<&str, Expression, ParserError> {
     Ok((input, Expression::Equals(Box::new(left), Box::new(right))))
 }
 
-fn parse_not_equals(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_not_equals(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("NE")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -311,7 +392,7 @@ fn parse_not_equals(input: &str) -> IResult<&str, Expression, ParserError> {
     ))
 }
 
-fn parse_greater_than(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_greater_than(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("GT")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -323,7 +404,7 @@ fn parse_greater_than(input: &str) -> IResult<&str, Expression, ParserError> {
     ))
 }
 
-fn parse_less_than(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_less_than(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("LT")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -332,7 +413,7 @@ fn parse_less_than(input: &str) -> IResult<&str, Expression, ParserError> {
     Ok((input, Expression::LessThan(Box::new(left), Box::new(right))))
 }
 
-fn parse_and(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_and(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("AND")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -341,7 +422,7 @@ fn parse_and(input: &str) -> IResult<&str, Expression, ParserError> {
     Ok((input, Expression::And(Box::new(left), Box::new(right))))
 }
 
-fn parse_or(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_or(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     let (input, _) = tag("OR")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, (left, _, right)) =
@@ -350,18 +431,24 @@ fn parse_or(input: &str) -> IResult<&str, Expression, ParserError> {
     Ok((input, Expression::Or(Box::new(left), Box::new(right))))
 }
 
-fn parse_expression(input: &str) -> IResult<&str, Expression, ParserError> {
+fn parse_expression(input: &str) -> IResult<&str, Expression, ParserError<'_>> {
     alt((parse_binary_ops, parse_value))(input)
 }
 
 /**
  * Statements
  */
-fn parse_statement(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_statement(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let _ = check_errors(input);
     debug("parsing new statement", &format!("{:#?}", input));
 
-    let pen_controls_group = alt((parse_penup, parse_pendown));
+    let pen_controls_group = alt((
+        parse_penup,
+        parse_pendown,
+        parse_penpaint,
+        parse_penerase,
+        parse_penreverse,
+    ));
 
     let turtle_movement_group = alt((
         parse_forward,
@@ -371,21 +458,71 @@ fn parse_statement(input: &str) -> IResult<&str, Statement, ParserError> {
         parse_turn,
     ));
 
-    let setters_group = alt((parse_setx, parse_sety, parse_setheading, parse_setpencolor));
+    let curves_group = alt((parse_curveto, parse_spline));
+
+    let setters_group = alt((
+        parse_setx,
+        parse_sety,
+        parse_setheading,
+        parse_setpencolor,
+        parse_setcoords,
+        parse_setbackground,
+    ));
+
+    let boundary_modes_group = alt((parse_wrap, parse_fence, parse_window));
+
+    let state_stack_group = alt((parse_pushstate, parse_popstate));
 
     let variable_assignment_group = alt((parse_make, parse_addassign));
 
     let control_structures_group = alt((parse_if, parse_while, parse_repeat));
 
+    let animation_group = alt((parse_frame, parse_wait));
+
+    let transforms_group = alt((
+        parse_scale,
+        parse_rotatecanvas,
+        parse_mirror,
+        parse_withtransform,
+    ));
+
+    let fills_group = alt((parse_setfillpattern, parse_filled));
+
+    let gradients_group = alt((parse_setpengradient, parse_setfillgradient));
+
+    let clipping_group = alt((parse_setclip, parse_clippolygon, parse_noclip));
+
+    let symmetry_group = parse_symmetry;
+
+    let shapes_group = alt((
+        parse_setshape,
+        parse_stamp,
+        parse_showturtle,
+        parse_hideturtle,
+    ));
+
+    let lsystem_group = parse_lsystem;
+
     let procedure_group = alt((parse_procedure_definition, parse_procedure_call));
 
     terminated(
         alt((
             pen_controls_group,
             turtle_movement_group,
+            curves_group,
             setters_group,
+            boundary_modes_group,
+            state_stack_group,
             variable_assignment_group,
             control_structures_group,
+            animation_group,
+            transforms_group,
+            fills_group,
+            gradients_group,
+            clipping_group,
+            symmetry_group,
+            shapes_group,
+            lsystem_group,
             procedure_group,
         )),
         multispace0,
@@ -395,22 +532,40 @@ fn parse_statement(input: &str) -> IResult<&str, Statement, ParserError> {
 /*
  * Pen control
  */
-fn parse_penup(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_penup(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("penup")(input)?;
 
     Ok((input, Statement::PenUp))
 }
 
-fn parse_pendown(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_pendown(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("pendown")(input)?;
 
     Ok((input, Statement::PenDown))
 }
 
+fn parse_penpaint(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("penpaint")(input)?;
+
+    Ok((input, Statement::PenPaint))
+}
+
+fn parse_penerase(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("penerase")(input)?;
+
+    Ok((input, Statement::PenErase))
+}
+
+fn parse_penreverse(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("penreverse")(input)?;
+
+    Ok((input, Statement::PenReverse))
+}
+
 /*
  * Movement control
  */
-fn parse_forward(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_forward(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("forward")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, pixels) = parse_expression(input)?;
@@ -418,7 +573,7 @@ fn parse_forward(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::Forward(Box::new(pixels))))
 }
 
-fn parse_back(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_back(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("back")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, pixels) = parse_expression(input)?;
@@ -426,7 +581,7 @@ fn parse_back(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::Back(Box::new(pixels))))
 }
 
-fn parse_left(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_left(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("left")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, degrees) = parse_expression(input)?;
@@ -434,7 +589,7 @@ fn parse_left(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::Left(Box::new(degrees))))
 }
 
-fn parse_right(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_right(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("right")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, degrees) = parse_expression(input)?;
@@ -442,7 +597,7 @@ fn parse_right(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::Right(Box::new(degrees))))
 }
 
-fn parse_turn(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_turn(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("turn")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, degrees) = parse_expression(input)?;
@@ -450,10 +605,28 @@ fn parse_turn(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::Turn(Box::new(degrees))))
 }
 
+/*
+ * Curves
+ */
+fn parse_curveto(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("curveto")(input)?;
+    let (input, points) = count(preceded(multispace1, parse_expression), 6)(input)?;
+
+    Ok((input, Statement::CurveTo(points)))
+}
+
+fn parse_spline(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("spline")(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, points) = parse_expression_list(input)?;
+
+    Ok((input, Statement::Spline(points)))
+}
+
 /*
  * Setters
  */
-fn parse_setx(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_setx(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("setx")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, position) = parse_expression(input)?;
@@ -461,7 +634,7 @@ fn parse_setx(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::SetX(Box::new(position))))
 }
 
-fn parse_sety(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_sety(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("sety")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, position) = parse_expression(input)?;
@@ -469,7 +642,7 @@ fn parse_sety(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::SetY(Box::new(position))))
 }
 
-fn parse_setheading(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_setheading(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("setheading")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, degrees) = parse_expression(input)?;
@@ -477,7 +650,7 @@ fn parse_setheading(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::SetHeading(Box::new(degrees))))
 }
 
-fn parse_setpencolor(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_setpencolor(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("setpencolor")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, color) = parse_expression(input)?;
@@ -485,10 +658,62 @@ fn parse_setpencolor(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::SetPenColor(Box::new(color))))
 }
 
+fn parse_setcoords(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("setcoords")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, system) = parse_expression(input)?;
+
+    Ok((input, Statement::SetCoords(Box::new(system))))
+}
+
+fn parse_setbackground(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("setbackground")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, color) = parse_expression(input)?;
+
+    Ok((input, Statement::SetBackground(Box::new(color))))
+}
+
+/*
+ * Boundary modes
+ */
+fn parse_wrap(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("wrap")(input)?;
+
+    Ok((input, Statement::Wrap))
+}
+
+fn parse_fence(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("fence")(input)?;
+
+    Ok((input, Statement::Fence))
+}
+
+fn parse_window(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("window")(input)?;
+
+    Ok((input, Statement::Window))
+}
+
+/*
+ * State stack
+ */
+fn parse_pushstate(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("pushstate")(input)?;
+
+    Ok((input, Statement::PushState))
+}
+
+fn parse_popstate(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("popstate")(input)?;
+
+    Ok((input, Statement::PopState))
+}
+
 /*
  * Variable assignment
  */
-fn parse_make(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_make(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("make")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, variable_name) = parse_identifier(input)?;
@@ -501,7 +726,7 @@ fn parse_make(input: &str) -> IResult<&str, Statement, ParserError> {
     ))
 }
 
-fn parse_addassign(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_addassign(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("addassign")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, variable_name) = parse_identifier(input)?;
@@ -517,7 +742,7 @@ fn parse_addassign(input: &str) -> IResult<&str, Statement, ParserError> {
 /*
  * Control structures
  */
-fn parse_if(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_if(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("if")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, condition) = parse_expression(input)?;
@@ -527,7 +752,7 @@ fn parse_if(input: &str) -> IResult<&str, Statement, ParserError> {
     Ok((input, Statement::If(Box::new(condition), Box::new(block))))
 }
 
-fn parse_while(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_while(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("while")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, condition) = parse_expression(input)?;
@@ -540,7 +765,7 @@ fn parse_while(input: &str) -> IResult<&str, Statement, ParserError> {
     ))
 }
 
-fn parse_repeat(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_repeat(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("repeat")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, condition) = parse_expression(input)?;
@@ -553,10 +778,325 @@ fn parse_repeat(input: &str) -> IResult<&str, Statement, ParserError> {
     ))
 }
 
+/*
+ * Animation
+ */
+fn parse_frame(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("frame")(input)?;
+
+    Ok((input, Statement::Frame))
+}
+
+fn parse_wait(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("wait")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, ticks) = parse_expression(input)?;
+
+    Ok((input, Statement::Wait(Box::new(ticks))))
+}
+
+/*
+ * L-systems
+ */
+enum LSystemLine {
+    Axiom(String),
+    Rule(LSystemRule),
+}
+
+fn parse_lsystem_symbols(input: &str) -> IResult<&str, &str, ParserError<'_>> {
+    // Symbols may contain balanced brackets (saving and restoring state), so only an unmatched ] ends them early
+    let depth = Cell::new(0);
+    let symbols = take_while1(move |c: char| match c {
+        '[' => {
+            depth.set(depth.get() + 1);
+            true
+        }
+        ']' if depth.get() == 0 => false,
+        ']' => {
+            depth.set(depth.get() - 1);
+            true
+        }
+        c => !c.is_whitespace(),
+    });
+
+    // Every [ must then be closed within the same symbols
+    cut(context(
+        "unbalanced brackets in L-system symbols",
+        verify(symbols, |symbols: &str| {
+            symbols.matches('[').count() == symbols.matches(']').count()
+        }),
+    ))(input)
+}
+
+fn parse_lsystem_axiom(input: &str) -> IResult<&str, LSystemLine, ParserError<'_>> {
+    let (input, _) = tag_no_case("axiom")(input)?;
+    let (input, _) = space1(input)?;
+    let (input, symbols) = parse_lsystem_symbols(input)?;
+
+    Ok((input, LSystemLine::Axiom(symbols.to_string())))
+}
+
+fn parse_lsystem_rule(input: &str) -> IResult<&str, LSystemLine, ParserError<'_>> {
+    let (input, _) = tag_no_case("rule")(input)?;
+    let (input, _) = space1(input)?;
+    let (input, symbol) = anychar(input)?;
+    let (input, _) = space1(input)?;
+    let (input, weight) = opt(terminated(preceded(tag("\""), digit1), space1))(input)?;
+    let (input, replacement) = parse_lsystem_symbols(input)?;
+
+    let weight = weight.and_then(|weight| weight.parse().ok()).unwrap_or(1);
+
+    Ok((
+        input,
+        LSystemLine::Rule(LSystemRule {
+            symbol,
+            weight,
+            replacement: replacement.to_string(),
+        }),
+    ))
+}
+
+fn parse_lsystem(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("lsystem")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, (iterations, _, angle, _, distance)) = tuple((
+        parse_expression,
+        multispace1,
+        parse_expression,
+        multispace1,
+        parse_expression,
+    ))(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, lines) = delimited(
+        tag("["),
+        many0(preceded(
+            multispace0,
+            alt((parse_lsystem_axiom, parse_lsystem_rule)),
+        )),
+        preceded(multispace0, tag("]")),
+    )(input)?;
+
+    let mut axiom = String::new();
+    let mut rules = Vec::new();
+    for line in lines {
+        match line {
+            LSystemLine::Axiom(symbols) => axiom = symbols,
+            LSystemLine::Rule(rule) => rules.push(rule),
+        }
+    }
+
+    Ok((
+        input,
+        Statement::LSystem {
+            iterations: Box::new(iterations),
+            angle: Box::new(angle),
+            distance: Box::new(distance),
+            axiom,
+            rules,
+        },
+    ))
+}
+
+/*
+ * Transforms
+ */
+fn parse_scale(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("scale")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, factor) = parse_expression(input)?;
+
+    // A divisor makes the factor a fraction (e.g. SCALE "1 "2 halves), and must be on the same line
+    let (input, divisor) = opt(preceded(space1, parse_expression))(input)?;
+
+    Ok((
+        input,
+        Statement::Scale {
+            factor: Box::new(factor),
+            divisor: divisor.map(Box::new),
+        },
+    ))
+}
+
+fn parse_rotatecanvas(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("rotatecanvas")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, degrees) = parse_expression(input)?;
+
+    Ok((input, Statement::RotateCanvas(Box::new(degrees))))
+}
+
+fn parse_mirror(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("mirror")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, axis) = parse_expression(input)?;
+
+    Ok((input, Statement::Mirror(Box::new(axis))))
+}
+
+fn parse_withtransform(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("withtransform")(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, block) = parse_block(input)?;
+
+    Ok((input, Statement::WithTransform(Box::new(block))))
+}
+
+/*
+ * Fills
+ */
+fn parse_setfillpattern(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("setfillpattern")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, (pattern, _, angle, _, spacing)) = tuple((
+        parse_expression,
+        multispace1,
+        parse_expression,
+        multispace1,
+        parse_expression,
+    ))(input)?;
+
+    Ok((
+        input,
+        Statement::SetFillPattern {
+            pattern: Box::new(pattern),
+            angle: Box::new(angle),
+            spacing: Box::new(spacing),
+        },
+    ))
+}
+
+fn parse_filled(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("filled")(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, block) = parse_block(input)?;
+
+    Ok((input, Statement::Filled(Box::new(block))))
+}
+
+/*
+ * Gradients
+ */
+fn parse_setpengradient(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("setpengradient")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, (from, _, to, _, steps)) = tuple((
+        parse_expression,
+        multispace1,
+        parse_expression,
+        multispace1,
+        parse_expression,
+    ))(input)?;
+
+    Ok((
+        input,
+        Statement::SetPenGradient {
+            from: Box::new(from),
+            to: Box::new(to),
+            steps: Box::new(steps),
+        },
+    ))
+}
+
+fn parse_setfillgradient(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("setfillgradient")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, (kind, _, from, _, to)) = tuple((
+        parse_expression,
+        multispace1,
+        parse_expression,
+        multispace1,
+        parse_expression,
+    ))(input)?;
+
+    // The angle of a linear gradient is optional, and must be on the same line
+    let (input, angle) = opt(preceded(space1, parse_expression))(input)?;
+
+    Ok((
+        input,
+        Statement::SetFillGradient {
+            kind: Box::new(kind),
+            from: Box::new(from),
+            to: Box::new(to),
+            angle: angle.map(Box::new),
+        },
+    ))
+}
+
+/*
+ * Clipping
+ */
+fn parse_setclip(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("setclip")(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, corners) = parse_expression_list(input)?;
+
+    Ok((input, Statement::SetClip(corners)))
+}
+
+fn parse_clippolygon(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("clippolygon")(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, points) = parse_expression_list(input)?;
+
+    Ok((input, Statement::ClipPolygon(points)))
+}
+
+fn parse_noclip(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("noclip")(input)?;
+
+    Ok((input, Statement::NoClip))
+}
+
+/*
+ * Symmetry
+ */
+fn parse_symmetry(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("symmetry")(input)?;
+    let (input, _) = multispace1(input)?;
+    let (input, mode) = parse_expression(input)?;
+
+    Ok((input, Statement::Symmetry(Box::new(mode))))
+}
+
+/*
+ * Shapes
+ */
+fn parse_setshape(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("setshape")(input)?;
+
+    alt((
+        map(
+            preceded(multispace0, parse_expression_list),
+            Statement::SetShapePoints,
+        ),
+        map(preceded(multispace1, parse_expression), |shape| {
+            Statement::SetShape(Box::new(shape))
+        }),
+    ))(input)
+}
+
+fn parse_stamp(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("stamp")(input)?;
+
+    Ok((input, Statement::Stamp))
+}
+
+fn parse_showturtle(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("showturtle")(input)?;
+
+    Ok((input, Statement::ShowTurtle))
+}
+
+fn parse_hideturtle(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
+    let (input, _) = tag_no_case("hideturtle")(input)?;
+
+    Ok((input, Statement::HideTurtle))
+}
+
 /*
  * Procedures
  */
-fn parse_procedure_definition(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_procedure_definition(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, _) = tag_no_case("to")(input)?;
     let (input, _) = multispace1(input)?;
     let (input, identifier) = parse_identifier(input)?;
@@ -568,7 +1108,11 @@ fn parse_procedure_definition(input: &str) -> IResult<&str, Statement, ParserErr
     let (input, _) = multispace0(input)?;
     let (input, body_string) = take_until("END\n")(input)?;
 
-    let (_, filtered) = parse_all(body_string.trim())?;
+    let (_, mut filtered) = parse_all(body_string.trim())?;
+
+    // The body is parsed on its own, so count the input after it in its markers too
+    let after_body = body_string.len() - body_string.trim_end().len() + input.len();
+    for_each_source_line(&mut filtered, &mut |marker| *marker += after_body);
 
     let (input, _) = multispace0(input)?;
     let (input, _) = tag_no_case("end")(input)?;
@@ -583,7 +1127,7 @@ fn parse_procedure_definition(input: &str) -> IResult<&str, Statement, ParserErr
     ))
 }
 
-fn parse_procedure_call(input: &str) -> IResult<&str, Statement, ParserError> {
+fn parse_procedure_call(input: &str) -> IResult<&str, Statement, ParserError<'_>> {
     let (input, identifier) = parse_identifier(input)?;
 
     let (input, parameters_string) = not_line_ending(input)?;
@@ -612,8 +1156,14 @@ fn parse_procedure_call(input: &str) -> IResult<&str, Statement, ParserError> {
 /*
  * Error handling for statements
  */
-fn check_keywords(input: &str) -> IResult<&str, &str, ParserError> {
-    let pen_controls_group = alt((tag_no_case("penup"), tag_no_case("pendown")));
+fn check_keywords(input: &str) -> IResult<&str, &str, ParserError<'_>> {
+    let pen_controls_group = alt((
+        tag_no_case("penup"),
+        tag_no_case("pendown"),
+        tag_no_case("penpaint"),
+        tag_no_case("penerase"),
+        tag_no_case("penreverse"),
+    ));
 
     let turtle_movement_group = alt((
         tag_no_case("forward"),
@@ -623,13 +1173,25 @@ fn check_keywords(input: &str) -> IResult<&str, &str, ParserError> {
         tag_no_case("turn"),
     ));
 
+    let curves_group = alt((tag_no_case("curveto"), tag_no_case("spline")));
+
     let setters_group = alt((
         tag_no_case("setx"),
         tag_no_case("sety"),
         tag_no_case("setheading"),
         tag_no_case("setpencolor"),
+        tag_no_case("setcoords"),
+        tag_no_case("setbackground"),
+    ));
+
+    let boundary_modes_group = alt((
+        tag_no_case("wrap"),
+        tag_no_case("fence"),
+        tag_no_case("window"),
     ));
 
+    let state_stack_group = alt((tag_no_case("pushstate"), tag_no_case("popstate")));
+
     let variable_assignment_group = alt((tag_no_case("make"), tag_no_case("addassign")));
 
     let control_structures_group = alt((
@@ -638,21 +1200,65 @@ fn check_keywords(input: &str) -> IResult<&str, &str, ParserError> {
         tag_no_case("repeat"),
     ));
 
+    let animation_group = alt((tag_no_case("frame"), tag_no_case("wait")));
+
+    let transforms_group = alt((
+        tag_no_case("scale"),
+        tag_no_case("rotatecanvas"),
+        tag_no_case("mirror"),
+        tag_no_case("withtransform"),
+    ));
+
+    let fills_group = alt((tag_no_case("setfillpattern"), tag_no_case("filled")));
+
+    let gradients_group = alt((
+        tag_no_case("setpengradient"),
+        tag_no_case("setfillgradient"),
+    ));
+
+    let clipping_group = alt((
+        tag_no_case("setclip"),
+        tag_no_case("clippolygon"),
+        tag_no_case("noclip"),
+    ));
+
+    let symmetry_group = tag_no_case("symmetry");
+
+    let shapes_group = alt((
+        tag_no_case("setshape"),
+        tag_no_case("stamp"),
+        tag_no_case("showturtle"),
+        tag_no_case("hideturtle"),
+    ));
+
+    let lsystem_group = tag_no_case("lsystem");
+
     let procedures_group = alt((tag_no_case("to"), tag_no_case("end")));
 
     let (input, keyword) = alt((
         pen_controls_group,
         turtle_movement_group,
+        curves_group,
         setters_group,
+        boundary_modes_group,
+        state_stack_group,
         variable_assignment_group,
         control_structures_group,
+        animation_group,
+        transforms_group,
+        fills_group,
+        gradients_group,
+        clipping_group,
+        symmetry_group,
+        shapes_group,
+        lsystem_group,
         procedures_group,
     ))(input)?;
 
     Ok((input, keyword))
 }
 
-fn check_errors(input: &str) -> IResult<&str, (), ParserError> {
+fn check_errors(input: &str) -> IResult<&str, (), ParserError<'_>> {
     let (_, (keyword, remaining)) = peek(tuple((check_keywords, not_line_ending)))(input)?;
 
     let (_, arguments) = parse_arguments(remaining)?;
@@ -670,11 +1276,23 @@ fn check_errors(input: &str) -> IResult<&str, (), ParserError> {
         );
     };
 
-    let print_error_argument_type = |expected_type: &str| {
+    let print_error_argument_range = |min_count: i32, max_count: i32| {
         print_error(
-            "incorrect argument type",
-            &format!("expected type {}", expected_type),
-            &[&format!(
+            "incorrect argument count",
+            &format!(
+                "{} to {} arguments expected, {} arguments given",
+                min_count, max_count, args_len
+            ),
+            &[&format!("check the syntax of the {} statement", keyword)],
+            true,
+        );
+    };
+
+    let print_error_argument_type = |expected_type: &str| {
+        print_error(
+            "incorrect argument type",
+            &format!("expected type {}", expected_type),
+            &[&format!(
                 "check the argument types of the {} statement",
                 keyword
             )],
@@ -683,13 +1301,15 @@ fn check_errors(input: &str) -> IResult<&str, (), ParserError> {
     };
 
     match keyword.to_lowercase().as_str() {
-        "penup" | "pendown" => {
-            if args_len != 0 {
-                print_error_argument_count(0);
-            }
+        "penup" | "pendown" | "penpaint" | "penerase" | "penreverse" | "frame" | "wrap"
+        | "fence" | "window" | "pushstate" | "popstate" | "stamp" | "showturtle" | "hideturtle"
+        | "withtransform" | "setclip" | "clippolygon" | "noclip" | "filled"
+            if args_len != 0 =>
+        {
+            print_error_argument_count(0);
         }
         "forward" | "back" | "left" | "right" | "turn" | "setx" | "sety" | "setheading"
-        | "setpencolor" => {
+        | "setpencolor" | "setbackground" | "wait" | "rotatecanvas" => {
             if args_len != 1 {
                 print_error_argument_count(1);
             }
@@ -700,11 +1320,74 @@ fn check_errors(input: &str) -> IResult<&str, (), ParserError> {
                 }
             }
         }
-        "make" | "addassign" => {
-            if args_len != 2 {
-                print_error_argument_count(2);
+        "scale" => {
+            if !(1..=2).contains(&args_len) {
+                print_error_argument_range(1, 2);
+            }
+
+            for arg in arguments {
+                if let Expression::StringLiteral(_) = arg {
+                    print_error_argument_type("non-string terminal value");
+                }
+            }
+        }
+        "setcoords" | "mirror" => {
+            if args_len != 1 {
+                print_error_argument_count(1);
+            }
+
+            for arg in arguments {
+                if let Expression::IntegerLiteral(_) = arg {
+                    print_error_argument_type("string");
+                }
+            }
+        }
+        "curveto" => {
+            if args_len != 6 {
+                print_error_argument_count(6);
+            }
+
+            for arg in arguments {
+                if let Expression::StringLiteral(_) = arg {
+                    print_error_argument_type("non-string terminal value");
+                }
             }
         }
+        "setshape" => {
+            // A custom shape is given as a list, which is not counted as an argument
+            if args_len > 1 {
+                print_error_argument_count(1);
+            }
+
+            for arg in arguments {
+                if let Expression::IntegerLiteral(_) = arg {
+                    print_error_argument_type("string");
+                }
+            }
+        }
+        "setfillpattern" | "setpengradient" if args_len != 3 => {
+            print_error_argument_count(3);
+        }
+        "setfillgradient" if !(3..=4).contains(&args_len) => {
+            print_error_argument_range(3, 4);
+        }
+        "symmetry" if args_len != 1 => {
+            print_error_argument_count(1);
+        }
+        "lsystem" => {
+            if args_len != 3 {
+                print_error_argument_count(3);
+            }
+
+            for arg in arguments {
+                if let Expression::StringLiteral(_) = arg {
+                    print_error_argument_type("non-string terminal value");
+                }
+            }
+        }
+        "make" | "addassign" if args_len != 2 => {
+            print_error_argument_count(2);
+        }
         "if" | "while" | "repeat" => {
             if args_len != 1 {
                 print_error_argument_count(2);
@@ -729,6 +1412,55 @@ fn check_errors(input: &str) -> IResult<&str, (), ParserError> {
 mod tests {
     use super::*;
 
+    /*
+     * Programs
+     */
+    #[test]
+    fn test_parse_program_source_lines() {
+        let input = "PENDOWN\n\nREPEAT \"2 [\n    FORWARD \"10\n]\n".to_string();
+        let expected = vec![
+            Statement::SourceLine(1),
+            Statement::PenDown,
+            Statement::SourceLine(3),
+            Statement::Repeat(
+                Box::new(Expression::IntegerLiteral(2)),
+                Box::new(vec![
+                    Statement::SourceLine(4),
+                    Statement::Forward(Box::new(Expression::IntegerLiteral(10))),
+                ]),
+            ),
+        ];
+        let result = parse_program(input);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_parse_program_source_lines_in_procedures() {
+        let input = "TO Square\n  PENDOWN\n\n  FORWARD \"10\nEND\n\nSquare\n".to_string();
+        let expected = vec![
+            Statement::SourceLine(1),
+            Statement::ProcedureDefinition {
+                name: Identifier("Square".to_string(), "".to_string()),
+                parameters: vec![],
+                body: vec![
+                    Statement::SourceLine(2),
+                    Statement::PenDown,
+                    Statement::SourceLine(4),
+                    Statement::Forward(Box::new(Expression::IntegerLiteral(10))),
+                ],
+            },
+            Statement::SourceLine(7),
+            Statement::ProcedureCall {
+                name: Identifier("Square".to_string(), "".to_string()),
+                arguments: vec![],
+            },
+        ];
+        let result = parse_program(input);
+
+        assert_eq!(result, expected);
+    }
+
     /*
      * Comments
      */
@@ -822,6 +1554,15 @@ mod tests {
         assert_eq!(result, Ok(("", expected)));
     }
 
+    #[test]
+    fn test_parse_penmode() {
+        let input = "PENMODE";
+        let expected = Expression::QueryPenMode;
+        let result = parse_penmode(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
     /*
      * Parentheses
      */
@@ -990,6 +1731,33 @@ mod tests {
         assert_eq!(result, Ok(("", expected)));
     }
 
+    #[test]
+    fn test_parse_penpaint() {
+        let input = "PENPAINT";
+        let expected = Statement::PenPaint;
+        let result = parse_penpaint(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_penerase() {
+        let input = "PENERASE";
+        let expected = Statement::PenErase;
+        let result = parse_penerase(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_penreverse() {
+        let input = "PENREVERSE";
+        let expected = Statement::PenReverse;
+        let result = parse_penreverse(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
     /*
      * Movement control
      */
@@ -1038,6 +1806,39 @@ mod tests {
         assert_eq!(result, Ok(("", expected)));
     }
 
+    /*
+     * Curves
+     */
+    #[test]
+    fn test_parse_curveto() {
+        let input = "CURVETO \"50 \"0 \"50 \"100 \"0 \"100";
+        let expected = Statement::CurveTo(vec![
+            Expression::IntegerLiteral(50),
+            Expression::IntegerLiteral(0),
+            Expression::IntegerLiteral(50),
+            Expression::IntegerLiteral(100),
+            Expression::IntegerLiteral(0),
+            Expression::IntegerLiteral(100),
+        ]);
+        let result = parse_curveto(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_spline() {
+        let input = "SPLINE [\"50 \"20 \"100 \"0]";
+        let expected = Statement::Spline(vec![
+            Expression::IntegerLiteral(50),
+            Expression::IntegerLiteral(20),
+            Expression::IntegerLiteral(100),
+            Expression::IntegerLiteral(0),
+        ]);
+        let result = parse_spline(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
     /*
      * Setters
      */
@@ -1077,6 +1878,76 @@ mod tests {
         assert_eq!(result, Ok(("", expected)));
     }
 
+    #[test]
+    fn test_parse_setcoords() {
+        let input = "SETCOORDS \"cartesian";
+        let expected =
+            Statement::SetCoords(Box::new(Expression::StringLiteral("cartesian".to_string())));
+        let result = parse_setcoords(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_setbackground() {
+        let input = "SETBACKGROUND \"3";
+        let expected = Statement::SetBackground(Box::new(Expression::IntegerLiteral(3)));
+        let result = parse_setbackground(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    /*
+     * Boundary modes
+     */
+    #[test]
+    fn test_parse_wrap() {
+        let input = "WRAP";
+        let expected = Statement::Wrap;
+        let result = parse_wrap(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_fence() {
+        let input = "FENCE";
+        let expected = Statement::Fence;
+        let result = parse_fence(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_window() {
+        let input = "WINDOW";
+        let expected = Statement::Window;
+        let result = parse_window(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    /*
+     * State stack
+     */
+    #[test]
+    fn test_parse_pushstate() {
+        let input = "PUSHSTATE";
+        let expected = Statement::PushState;
+        let result = parse_pushstate(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_popstate() {
+        let input = "POPSTATE";
+        let expected = Statement::PopState;
+        let result = parse_popstate(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
     /*
      * Variable assignment
      */
@@ -1115,7 +1986,7 @@ mod tests {
                 Box::new(Expression::IntegerLiteral(10)),
                 Box::new(Expression::IntegerLiteral(20)),
             )),
-            Box::new(vec![Statement::PenUp]),
+            Box::new(vec![Statement::SourceLine(6), Statement::PenUp]),
         );
         let result = parse_if(input);
 
@@ -1130,7 +2001,7 @@ mod tests {
                 Box::new(Expression::IntegerLiteral(10)),
                 Box::new(Expression::IntegerLiteral(20)),
             )),
-            Box::new(vec![Statement::PenUp]),
+            Box::new(vec![Statement::SourceLine(6), Statement::PenUp]),
         );
         let result = parse_while(input);
 
@@ -1142,13 +2013,343 @@ mod tests {
         let input = "REPEAT \"10 [PENUP]";
         let expected = Statement::Repeat(
             Box::new(Expression::IntegerLiteral(10)),
-            Box::new(vec![Statement::PenUp]),
+            Box::new(vec![Statement::SourceLine(6), Statement::PenUp]),
         );
         let result = parse_repeat(input);
 
         assert_eq!(result, Ok(("", expected)));
     }
 
+    /*
+     * Animation
+     */
+    #[test]
+    fn test_parse_frame() {
+        let input = "FRAME";
+        let expected = Statement::Frame;
+        let result = parse_frame(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_wait() {
+        let input = "WAIT \"30";
+        let expected = Statement::Wait(Box::new(Expression::IntegerLiteral(30)));
+        let result = parse_wait(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    /*
+     * Transforms
+     */
+    #[test]
+    fn test_parse_scale() {
+        let input = "SCALE \"2";
+        let expected = Statement::Scale {
+            factor: Box::new(Expression::IntegerLiteral(2)),
+            divisor: None,
+        };
+        let result = parse_scale(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_scale_with_divisor() {
+        let input = "SCALE \"1 \"2";
+        let expected = Statement::Scale {
+            factor: Box::new(Expression::IntegerLiteral(1)),
+            divisor: Some(Box::new(Expression::IntegerLiteral(2))),
+        };
+        let result = parse_scale(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_rotatecanvas() {
+        let input = "ROTATECANVAS \"45";
+        let expected = Statement::RotateCanvas(Box::new(Expression::IntegerLiteral(45)));
+        let result = parse_rotatecanvas(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_mirror() {
+        let input = "MIRROR \"x";
+        let expected = Statement::Mirror(Box::new(Expression::StringLiteral("x".to_string())));
+        let result = parse_mirror(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_withtransform() {
+        let input = "WITHTRANSFORM [SCALE \"2]";
+        let expected = Statement::WithTransform(Box::new(vec![
+            Statement::SourceLine(9),
+            Statement::Scale {
+                factor: Box::new(Expression::IntegerLiteral(2)),
+                divisor: None,
+            },
+        ]));
+        let result = parse_withtransform(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    /*
+     * Fills
+     */
+    #[test]
+    fn test_parse_setfillpattern() {
+        let input = "SETFILLPATTERN \"crosshatch \"30 \"4";
+        let expected = Statement::SetFillPattern {
+            pattern: Box::new(Expression::StringLiteral("crosshatch".to_string())),
+            angle: Box::new(Expression::IntegerLiteral(30)),
+            spacing: Box::new(Expression::IntegerLiteral(4)),
+        };
+        let result = parse_setfillpattern(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_filled() {
+        let input = "FILLED [FORWARD \"10]";
+        let expected = Statement::Filled(Box::new(vec![
+            Statement::SourceLine(12),
+            Statement::Forward(Box::new(Expression::IntegerLiteral(10))),
+        ]));
+        let result = parse_filled(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    /*
+     * Gradients
+     */
+    #[test]
+    fn test_parse_setpengradient() {
+        let input = "SETPENGRADIENT \"1 \"4 \"36";
+        let expected = Statement::SetPenGradient {
+            from: Box::new(Expression::IntegerLiteral(1)),
+            to: Box::new(Expression::IntegerLiteral(4)),
+            steps: Box::new(Expression::IntegerLiteral(36)),
+        };
+        let result = parse_setpengradient(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_setfillgradient() {
+        let input = "SETFILLGRADIENT \"linear \"1 \"4 \"90";
+        let expected = Statement::SetFillGradient {
+            kind: Box::new(Expression::StringLiteral("linear".to_string())),
+            from: Box::new(Expression::IntegerLiteral(1)),
+            to: Box::new(Expression::IntegerLiteral(4)),
+            angle: Some(Box::new(Expression::IntegerLiteral(90))),
+        };
+        let result = parse_setfillgradient(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_setfillgradient_without_angle() {
+        let input = "SETFILLGRADIENT \"radial \"1 \"4\nFORWARD \"10";
+        let expected = Statement::SetFillGradient {
+            kind: Box::new(Expression::StringLiteral("radial".to_string())),
+            from: Box::new(Expression::IntegerLiteral(1)),
+            to: Box::new(Expression::IntegerLiteral(4)),
+            angle: None,
+        };
+        let result = parse_setfillgradient(input);
+
+        assert_eq!(result, Ok(("\nFORWARD \"10", expected)));
+    }
+
+    /*
+     * Clipping
+     */
+    #[test]
+    fn test_parse_setclip() {
+        let input = "SETCLIP [\"0 \"0 \"100 \"50]";
+        let expected = Statement::SetClip(vec![
+            Expression::IntegerLiteral(0),
+            Expression::IntegerLiteral(0),
+            Expression::IntegerLiteral(100),
+            Expression::IntegerLiteral(50),
+        ]);
+        let result = parse_setclip(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_clippolygon() {
+        let input = "CLIPPOLYGON [\"0 \"0 \"100 \"0 \"50 \"80]";
+        let expected = Statement::ClipPolygon(vec![
+            Expression::IntegerLiteral(0),
+            Expression::IntegerLiteral(0),
+            Expression::IntegerLiteral(100),
+            Expression::IntegerLiteral(0),
+            Expression::IntegerLiteral(50),
+            Expression::IntegerLiteral(80),
+        ]);
+        let result = parse_clippolygon(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_noclip() {
+        let input = "NOCLIP";
+        let expected = Statement::NoClip;
+        let result = parse_noclip(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    /*
+     * Symmetry
+     */
+    #[test]
+    fn test_parse_symmetry() {
+        let input = "SYMMETRY \"6";
+        let expected = Statement::Symmetry(Box::new(Expression::IntegerLiteral(6)));
+        let result = parse_symmetry(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_symmetry_mirror() {
+        let input = "SYMMETRY \"mirror";
+        let expected =
+            Statement::Symmetry(Box::new(Expression::StringLiteral("mirror".to_string())));
+        let result = parse_symmetry(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    /*
+     * Shapes
+     */
+    #[test]
+    fn test_parse_setshape() {
+        let input = "SETSHAPE \"turtle";
+        let expected =
+            Statement::SetShape(Box::new(Expression::StringLiteral("turtle".to_string())));
+        let result = parse_setshape(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_setshape_points() {
+        let input = "SETSHAPE [\"10 \"0 \"-5 \"5]";
+        let expected = Statement::SetShapePoints(vec![
+            Expression::IntegerLiteral(10),
+            Expression::IntegerLiteral(0),
+            Expression::IntegerLiteral(-5),
+            Expression::IntegerLiteral(5),
+        ]);
+        let result = parse_setshape(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_stamp() {
+        let input = "STAMP";
+        let expected = Statement::Stamp;
+        let result = parse_stamp(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_showturtle() {
+        let input = "SHOWTURTLE";
+        let expected = Statement::ShowTurtle;
+        let result = parse_showturtle(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_hideturtle() {
+        let input = "HIDETURTLE";
+        let expected = Statement::HideTurtle;
+        let result = parse_hideturtle(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    /*
+     * L-systems
+     */
+    #[test]
+    fn test_parse_lsystem() {
+        let input = "LSYSTEM \"4 \"25 \"5 [\n    AXIOM X\n    RULE X F+[[X]-X]-F[-X]+X\n    RULE F \"2 FF\n]";
+        let expected = Statement::LSystem {
+            iterations: Box::new(Expression::IntegerLiteral(4)),
+            angle: Box::new(Expression::IntegerLiteral(25)),
+            distance: Box::new(Expression::IntegerLiteral(5)),
+            axiom: "X".to_string(),
+            rules: vec![
+                LSystemRule {
+                    symbol: 'X',
+                    weight: 1,
+                    replacement: "F+[[X]-X]-F[-X]+X".to_string(),
+                },
+                LSystemRule {
+                    symbol: 'F',
+                    weight: 2,
+                    replacement: "FF".to_string(),
+                },
+            ],
+        };
+        let result = parse_lsystem(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_lsystem_closing_bracket_after_rule() {
+        let input = "LSYSTEM \"2 \"90 \"5 [\n    AXIOM F\n    RULE F F[+F]F]";
+        let expected = Statement::LSystem {
+            iterations: Box::new(Expression::IntegerLiteral(2)),
+            angle: Box::new(Expression::IntegerLiteral(90)),
+            distance: Box::new(Expression::IntegerLiteral(5)),
+            axiom: "F".to_string(),
+            rules: vec![LSystemRule {
+                symbol: 'F',
+                weight: 1,
+                replacement: "F[+F]F".to_string(),
+            }],
+        };
+        let result = parse_lsystem(input);
+
+        assert_eq!(result, Ok(("", expected)));
+    }
+
+    #[test]
+    fn test_parse_lsystem_unbalanced_brackets() {
+        let input = "LSYSTEM \"2 \"90 \"5 [\n    AXIOM F\n    RULE F F[+F\n]";
+        let result = parse_lsystem(input);
+
+        assert!(matches!(result, Err(nom::Err::Failure(_))));
+    }
+
+    /*
+     * Procedures
+     */
     #[test]
     fn test_parse_procedure_definition() {
         let input = "TO Foo :bar \"baz\nPENUP\nEND\n";
@@ -1158,7 +2359,7 @@ mod tests {
                 Expression::VariableReference("bar".to_string()),
                 Expression::StringLiteral("baz".to_string()),
             ],
-            body: vec![Statement::PenUp],
+            body: vec![Statement::SourceLine(10), Statement::PenUp],
         };
         let result = parse_procedure_definition(input);
 
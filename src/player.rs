@@ -0,0 +1,264 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * player.rs - Self-contained HTML player for stepping through a drawing
+ * ========================================================================
+ */
+
+use unsvg::COLORS;
+
+use crate::render::rgb_hex;
+use crate::turtle::{PenMode, Segment};
+
+use std::fmt::Write;
+
+/**
+ * Inline stylesheet of the player page
+ */
+const PLAYER_STYLE: &str = r##"
+body { margin: 0; padding: 16px; display: flex; gap: 16px; align-items: flex-start; font-family: sans-serif; background: #202020; color: #e0e0e0; }
+#player { display: flex; flex-direction: column; gap: 8px; }
+#controls { display: flex; gap: 8px; align-items: center; }
+#timeline { flex: 1; }
+#source { margin: 0; max-height: 90vh; overflow: auto; font-family: monospace; background: #101010; padding: 8px 0; min-width: 320px; }
+.line { padding: 0 12px; white-space: pre; }
+.line::before { content: attr(data-line); display: inline-block; width: 3em; color: #707070; }
+.line.active { background: #505000; }
+"##;
+
+/**
+ * Inline script of the player page
+ *
+ * Expects WIDTH, HEIGHT, BACKGROUND, COLORS, SEGMENTS ([x1, y1, x2, y2, pen_down, color, line, mode, controls?], where color is a CSS colour) and SOURCE to be defined
+ */
+const PLAYER_SCRIPT: &str = r##"
+const canvas = document.getElementById("canvas");
+const context = canvas.getContext("2d");
+const play = document.getElementById("play");
+const timeline = document.getElementById("timeline");
+const position = document.getElementById("position");
+const source = document.getElementById("source");
+
+const lines = SOURCE.map((text, i) => {
+    const line = document.createElement("div");
+    line.className = "line";
+    line.dataset.line = i + 1;
+    line.textContent = text;
+    source.appendChild(line);
+    return line;
+});
+
+let step = 0;
+let playing = false;
+let active = null;
+
+function render() {
+    context.fillStyle = COLORS[BACKGROUND];
+    context.fillRect(0, 0, WIDTH, HEIGHT);
+    context.lineWidth = 1;
+
+    for (const [x1, y1, x2, y2, penDown, color, , mode, controls] of SEGMENTS.slice(0, step)) {
+        if (penDown) {
+            // Pen modes: 0 paints, 1 erases with the background and 2 inverts whatever is underneath
+            context.strokeStyle = mode === 1 ? COLORS[BACKGROUND] : mode === 2 ? "#ffffff" : color;
+            context.globalCompositeOperation = mode === 2 ? "difference" : "source-over";
+            context.beginPath();
+            context.moveTo(x1, y1);
+            if (controls) {
+                context.bezierCurveTo(...controls, x2, y2);
+            } else {
+                context.lineTo(x2, y2);
+            }
+            context.stroke();
+        }
+    }
+    context.globalCompositeOperation = "source-over";
+
+    const current = SEGMENTS[step - 1];
+    if (current) {
+        context.fillStyle = current[5];
+        context.beginPath();
+        context.arc(current[2], current[3], 3, 0, 2 * Math.PI);
+        context.fill();
+    }
+
+    if (active) {
+        active.classList.remove("active");
+    }
+    active = current && current[6] > 0 ? lines[current[6] - 1] : null;
+    if (active) {
+        active.classList.add("active");
+        active.scrollIntoView({ block: "nearest" });
+    }
+
+    timeline.value = step;
+    position.textContent = step + " / " + SEGMENTS.length;
+}
+
+function tick() {
+    if (!playing) {
+        return;
+    }
+
+    step = Math.min(step + Math.max(1, Math.floor(SEGMENTS.length / 600)), SEGMENTS.length);
+    render();
+
+    if (step >= SEGMENTS.length) {
+        playing = false;
+        play.textContent = "Play";
+    } else {
+        requestAnimationFrame(tick);
+    }
+}
+
+play.addEventListener("click", () => {
+    playing = !playing;
+    play.textContent = playing ? "Pause" : "Play";
+
+    if (playing) {
+        if (step >= SEGMENTS.length) {
+            step = 0;
+        }
+        requestAnimationFrame(tick);
+    }
+});
+
+timeline.addEventListener("input", () => {
+    step = Number(timeline.value);
+    render();
+});
+
+timeline.max = SEGMENTS.length;
+render();
+"##;
+
+/**
+ * Escape a string so it can be embedded as a JSON (and therefore JavaScript) string literal
+ *
+ * Forward slashes are escaped as well, so the string can never close the surrounding script tag
+ */
+pub fn json_string(value: &str) -> String {
+    let mut output = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '/' => output.push_str("\\/"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+/**
+ * Generate a self-contained HTML page that replays the drawing step by step
+ *
+ * The page embeds every recorded movement and the program source, with a player that has play/pause, a timeline
+ * slider and a highlight of the source line that made the current movement. No server or external files are needed
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * width: u32 - The width of the canvas in pixels
+ * height: u32 - The height of the canvas in pixels
+ * background: i32 - The color (0-15) of the canvas background
+ * source: &str - The source code of the program
+ *
+ * Returns:
+ * String - The HTML page
+ */
+pub fn to_html(
+    segments: &[Segment],
+    width: u32,
+    height: u32,
+    background: i32,
+    source: &str,
+) -> String {
+    let colors: Vec<String> = COLORS
+        .iter()
+        .map(|color| {
+            format!(
+                "\"#{:02x}{:02x}{:02x}\"",
+                color.red, color.green, color.blue
+            )
+        })
+        .collect();
+
+    let segments: Vec<String> = segments
+        .iter()
+        .map(|segment| {
+            // Curves carry their control points as a ninth element
+            let controls = match segment.controls {
+                Some((cx1, cy1, cx2, cy2)) => format!(",[{},{},{},{}]", cx1, cy1, cx2, cy2),
+                None => String::new(),
+            };
+
+            format!(
+                "[{},{},{},{},{},\"{}\",{},{}{}]",
+                segment.x1,
+                segment.y1,
+                segment.x2,
+                segment.y2,
+                segment.pen_down as i32,
+                rgb_hex(segment.rgb()),
+                segment.line,
+                match segment.mode {
+                    PenMode::Paint => 0,
+                    PenMode::Erase => 1,
+                    PenMode::Reverse => 2,
+                },
+                controls
+            )
+        })
+        .collect();
+
+    let source: Vec<String> = source.lines().map(json_string).collect();
+
+    let mut output = String::new();
+    let _ = writeln!(output, "<!DOCTYPE html>");
+    let _ = writeln!(output, "<html>");
+    let _ = writeln!(output, "<head>");
+    let _ = writeln!(output, "<meta charset=\"utf-8\">");
+    let _ = writeln!(output, "<title>rslogo player</title>");
+    let _ = writeln!(output, "<style>{}</style>", PLAYER_STYLE);
+    let _ = writeln!(output, "</head>");
+    let _ = writeln!(output, "<body>");
+    let _ = writeln!(output, "<div id=\"player\">");
+    let _ = writeln!(
+        output,
+        "<canvas id=\"canvas\" width=\"{}\" height=\"{}\"></canvas>",
+        width, height
+    );
+    let _ = writeln!(output, "<div id=\"controls\">");
+    let _ = writeln!(output, "<button id=\"play\">Play</button>");
+    let _ = writeln!(
+        output,
+        "<input id=\"timeline\" type=\"range\" min=\"0\" value=\"0\">"
+    );
+    let _ = writeln!(output, "<span id=\"position\"></span>");
+    let _ = writeln!(output, "</div>");
+    let _ = writeln!(output, "</div>");
+    let _ = writeln!(output, "<pre id=\"source\"></pre>");
+    let _ = writeln!(output, "<script>");
+    let _ = writeln!(output, "const WIDTH = {};", width);
+    let _ = writeln!(output, "const HEIGHT = {};", height);
+    let _ = writeln!(output, "const BACKGROUND = {};", background);
+    let _ = writeln!(output, "const COLORS = [{}];", colors.join(","));
+    let _ = writeln!(output, "const SEGMENTS = [{}];", segments.join(","));
+    let _ = writeln!(output, "const SOURCE = [{}];", source.join(","));
+    let _ = writeln!(output, "{}", PLAYER_SCRIPT);
+    let _ = writeln!(output, "</script>");
+    let _ = writeln!(output, "</body>");
+    let _ = writeln!(output, "</html>");
+
+    output
+}
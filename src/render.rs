@@ -0,0 +1,1274 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * render.rs - SVG and raster rendering of the turtle's segments
+ * ========================================================================
+ */
+
+use tiny_skia::{
+    BlendMode, Color, FillRule, FilterQuality, GradientStop, LinearGradient, Mask, Paint,
+    PathBuilder, Pixmap, PixmapPaint, Point, RadialGradient, SpreadMode, Stroke, Transform,
+};
+
+use crate::fill::{Fill, FillGradient};
+use crate::turtle::{palette_rgb, PenMode, ProcedureCall, Segment};
+
+use std::fmt::Write;
+use std::path::Path;
+
+/**
+ * Number of decimal places coordinates are written with in SVG output, unless set with --precision
+ */
+pub const DEFAULT_PRECISION: usize = 3;
+
+/**
+ * Characters of the base64 alphabet, indexed by their 6-bit value
+ */
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/**
+ * Represents a reference image drawn underneath the drawing, stretched over the whole canvas
+ *
+ * Properties:
+ * png: Vec<u8> - The PNG file contents, embedded as-is in SVG output
+ * pixmap: Pixmap - The decoded image, drawn in raster output
+ * width: u32 - The width of the canvas the image covers
+ * height: u32 - The height of the canvas the image covers
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Underlay {
+    pub png: Vec<u8>,
+    pub pixmap: Pixmap,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Underlay {
+    /**
+     * Load a PNG image to cover a canvas of the given size
+     */
+    pub fn load(path: &Path, width: u32, height: u32) -> Result<Self, String> {
+        let png = std::fs::read(path).map_err(|e| e.to_string())?;
+        let pixmap = Pixmap::decode_png(&png).map_err(|e| e.to_string())?;
+
+        Ok(Underlay {
+            png,
+            pixmap,
+            width,
+            height,
+        })
+    }
+}
+
+/**
+ * Represents the region of the canvas that is shown in the output image, and what is shown behind the drawing
+ *
+ * Properties:
+ * x: f64 - The x-coordinate of the top left corner of the region
+ * y: f64 - The y-coordinate of the top left corner of the region
+ * width: f64 - The width of the region
+ * height: f64 - The height of the region
+ * output_width: u32 - The width of the output image in pixels
+ * output_height: u32 - The height of the output image in pixels
+ * background: i32 - The color (0-15) of the canvas background
+ * underlay: Option<Underlay> - The reference image drawn underneath the drawing, if any
+ * clips: Vec<Vec<(f64, f64)>> - The clipping regions lines may be drawn in, as polygons in canvas coordinates
+ * fills: Vec<Fill> - The shapes filled with gradients, drawn in between the lines
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub output_width: u32,
+    pub output_height: u32,
+    pub background: i32,
+    pub underlay: Option<Underlay>,
+    pub clips: Vec<Vec<(f64, f64)>>,
+    pub fills: Vec<Fill>,
+}
+
+impl View {
+    /**
+     * The whole canvas, shown at its original size
+     */
+    pub fn canvas(width: u32, height: u32) -> Self {
+        View {
+            x: 0.0,
+            y: 0.0,
+            width: width as f64,
+            height: height as f64,
+            output_width: width,
+            output_height: height,
+            background: 0,
+            underlay: None,
+            clips: Vec::new(),
+            fills: Vec::new(),
+        }
+    }
+
+    /**
+     * The bounding box of every drawn line, scaled to fit an output image of the canvas' size
+     *
+     * The region is widened or heightened around its centre to match the shape of the canvas, so the drawing is
+     * never stretched. Falls back to the whole canvas if nothing was drawn
+     */
+    pub fn fit(segments: &[Segment], width: u32, height: u32, margin: f64) -> Self {
+        let (min_x, min_y, max_x, max_y) = match bounding_box(segments) {
+            Some(bounds) => bounds,
+            None => return View::canvas(width, height),
+        };
+
+        let (mut region_width, mut region_height) =
+            (max_x - min_x + 2.0 * margin, max_y - min_y + 2.0 * margin);
+        let (center_x, center_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+
+        let aspect = width as f64 / height as f64;
+        if region_width / region_height < aspect {
+            region_width = region_height * aspect;
+        } else {
+            region_height = region_width / aspect;
+        }
+
+        View {
+            x: center_x - region_width / 2.0,
+            y: center_y - region_height / 2.0,
+            width: region_width,
+            height: region_height,
+            output_width: width,
+            output_height: height,
+            background: 0,
+            underlay: None,
+            clips: Vec::new(),
+            fills: Vec::new(),
+        }
+    }
+
+    /**
+     * The bounding box of every drawn line, shown at its original size
+     *
+     * The output image grows or shrinks to fit the drawing. Falls back to the whole canvas if nothing was drawn
+     */
+    pub fn infinite(segments: &[Segment], width: u32, height: u32, margin: f64) -> Self {
+        let (min_x, min_y, max_x, max_y) = match bounding_box(segments) {
+            Some(bounds) => bounds,
+            None => return View::canvas(width, height),
+        };
+
+        // Keep whole pixel boundaries, so lines land on the same pixels as on a fixed canvas
+        let (x, y) = ((min_x - margin).floor(), (min_y - margin).floor());
+        let output_width = ((max_x + margin).ceil() - x).max(1.0);
+        let output_height = ((max_y + margin).ceil() - y).max(1.0);
+
+        View {
+            x,
+            y,
+            width: output_width,
+            height: output_height,
+            output_width: output_width as u32,
+            output_height: output_height as u32,
+            background: 0,
+            underlay: None,
+            clips: Vec::new(),
+            fills: Vec::new(),
+        }
+    }
+
+    /**
+     * The transformation from canvas coordinates to output image pixels
+     */
+    pub fn transform(&self) -> Transform {
+        let scale_x = self.output_width as f64 / self.width;
+        let scale_y = self.output_height as f64 / self.height;
+
+        Transform::from_row(
+            scale_x as f32,
+            0.0,
+            0.0,
+            scale_y as f32,
+            (-self.x * scale_x) as f32,
+            (-self.y * scale_y) as f32,
+        )
+    }
+}
+
+/**
+ * The smallest rectangle (min_x, min_y, max_x, max_y) containing every drawn line, if any were drawn
+ */
+pub fn bounding_box(segments: &[Segment]) -> Option<(f64, f64, f64, f64)> {
+    segments
+        .iter()
+        .filter(|segment| segment.pen_down)
+        .flat_map(Segment::lines)
+        .fold(None, |bounds, segment| {
+            let (min_x, min_y, max_x, max_y) =
+                bounds.unwrap_or((segment.x1, segment.y1, segment.x1, segment.y1));
+
+            Some((
+                min_x.min(segment.x1).min(segment.x2),
+                min_y.min(segment.y1).min(segment.y2),
+                max_x.max(segment.x1).max(segment.x2),
+                max_y.max(segment.y1).max(segment.y2),
+            ))
+        })
+}
+
+/*
+ * SVG
+ */
+
+/**
+ * Format a number for SVG output, with at most 3 decimal places and no trailing zeros
+ */
+pub fn format_number(value: f64) -> String {
+    format_decimal(value, DEFAULT_PRECISION)
+}
+
+/**
+ * Format a number with at most the given number of decimal places and no trailing zeros
+ */
+pub fn format_decimal(value: f64, precision: usize) -> String {
+    let formatted = format!("{:.*}", precision, value);
+    let trimmed = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &formatted
+    };
+
+    match trimmed {
+        "-0" | "" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/**
+ * The hexadecimal notation of a pen color (0-15), e.g. #ff00ff
+ */
+pub fn color_hex(color: i32) -> String {
+    rgb_hex(palette_rgb(color))
+}
+
+/**
+ * The hexadecimal notation of a colour given as (red, green, blue), e.g. #ff00ff
+ */
+pub fn rgb_hex((red, green, blue): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", red, green, blue)
+}
+
+/**
+ * Encode binary data as base64, e.g. for embedding images in data URIs
+ */
+fn base64(data: &[u8]) -> String {
+    let mut output = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(BASE64_ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+
+    output
+}
+
+/**
+ * The opening tag, definitions, background and underlay of an SVG image of the given view
+ */
+pub fn svg_header(view: &View) -> String {
+    let (x, y) = (format_number(view.x), format_number(view.y));
+    let (right, bottom) = (
+        format_number(view.x + view.width),
+        format_number(view.y + view.height),
+    );
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "<svg width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\" xmlns=\"http://www.w3.org/2000/svg\">",
+        view.output_width,
+        view.output_height,
+        x,
+        y,
+        format_number(view.width),
+        format_number(view.height)
+    );
+    if view.clips.is_empty() && view.fills.is_empty() {
+        let _ = writeln!(output, "    <defs/>");
+    } else {
+        let _ = writeln!(output, "    <defs>");
+        for (i, polygon) in view.clips.iter().enumerate() {
+            let points: Vec<String> = polygon
+                .iter()
+                .map(|&(x, y)| format!("{} {}", format_number(x), format_number(y)))
+                .collect();
+
+            let _ = writeln!(
+                output,
+                "        <clipPath id=\"clip{}\"><path d=\"M {} Z\"/></clipPath>",
+                i,
+                points.join(" L ")
+            );
+        }
+        for (i, fill) in view.fills.iter().enumerate() {
+            let _ = writeln!(output, "        {}", svg_gradient(fill, i));
+        }
+        let _ = writeln!(output, "    </defs>");
+    }
+    let _ = writeln!(
+        output,
+        "    <path fill=\"{4}\" stroke=\"none\" d=\"M {0} {1} L {2} {1} L {2} {3} L {0} {3} Z\"/>",
+        x,
+        y,
+        right,
+        bottom,
+        color_hex(view.background)
+    );
+
+    if let Some(underlay) = &view.underlay {
+        let _ = writeln!(
+            output,
+            "    <image x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\" href=\"data:image/png;base64,{}\"/>",
+            underlay.width,
+            underlay.height,
+            base64(&underlay.png)
+        );
+    }
+
+    output
+}
+
+/**
+ * The attributes of the SVG path element for a single drawn line or curve, with coordinates given to `precision`
+ * decimal places
+ *
+ * Reversing lines are blended with a difference, which inverts whatever is underneath them
+ */
+pub fn svg_line_attributes(segment: &Segment, background: i32, precision: usize) -> String {
+    let format_number = |value: f64| format_decimal(value, precision);
+    let blend = match segment.mode {
+        PenMode::Reverse => " style=\"mix-blend-mode:difference\"",
+        PenMode::Paint | PenMode::Erase => "",
+    };
+
+    let to = match segment.controls {
+        Some((cx1, cy1, cx2, cy2)) => format!(
+            "C {} {} {} {}",
+            format_number(cx1),
+            format_number(cy1),
+            format_number(cx2),
+            format_number(cy2)
+        ),
+        None => "L".to_string(),
+    };
+
+    let clip = match segment.clip {
+        Some(index) => format!(" clip-path=\"url(#clip{})\"", index),
+        None => String::new(),
+    };
+
+    format!(
+        "fill=\"none\" stroke=\"{}\"{}{} d=\"M {} {} {} {} {}\"",
+        rgb_hex(segment.stroke_rgb(background)),
+        blend,
+        clip,
+        format_number(segment.x1),
+        format_number(segment.y1),
+        to,
+        format_number(segment.x2),
+        format_number(segment.y2)
+    )
+}
+
+/**
+ * The SVG gradient definition for a shape filled with a gradient, referred to as `fillN` by its path element
+ */
+fn svg_gradient(fill: &Fill, index: usize) -> String {
+    let (from, to) = fill.gradient.colors();
+    let stops = format!(
+        "<stop offset=\"0\" stop-color=\"{}\"/><stop offset=\"1\" stop-color=\"{}\"/>",
+        color_hex(from),
+        color_hex(to)
+    );
+
+    match fill.gradient {
+        FillGradient::Linear { angle, .. } => {
+            let ((x1, y1), (x2, y2)) = fill.linear_ends(angle);
+
+            format!(
+                "<linearGradient id=\"fill{}\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">{}</linearGradient>",
+                index,
+                format_number(x1),
+                format_number(y1),
+                format_number(x2),
+                format_number(y2),
+                stops
+            )
+        }
+        FillGradient::Radial { .. } => {
+            let ((cx, cy), radius) = fill.radial_circle();
+
+            format!(
+                "<radialGradient id=\"fill{}\" gradientUnits=\"userSpaceOnUse\" cx=\"{}\" cy=\"{}\" r=\"{}\">{}</radialGradient>",
+                index,
+                format_number(cx),
+                format_number(cy),
+                format_number(radius),
+                stops
+            )
+        }
+    }
+}
+
+/**
+ * The attributes of the SVG path element for a shape filled with a gradient
+ */
+fn svg_fill_attributes(fill: &Fill, index: usize, precision: usize) -> String {
+    let format_number = |value: f64| format_decimal(value, precision);
+    let points: Vec<String> = fill
+        .polygon
+        .iter()
+        .map(|&(x, y)| format!("{} {}", format_number(x), format_number(y)))
+        .collect();
+
+    let clip = match fill.clip {
+        Some(clip) => format!(" clip-path=\"url(#clip{})\"", clip),
+        None => String::new(),
+    };
+
+    format!(
+        "fill=\"url(#fill{})\" stroke=\"none\"{} d=\"M {} Z\"",
+        index,
+        clip,
+        points.join(" L ")
+    )
+}
+
+/**
+ * Represents the user-configurable settings for SVG output
+ *
+ * Properties:
+ * precision: usize - The number of decimal places coordinates of drawn lines are written with
+ * compact: bool - Whether connected lines are joined into single elements, grouped by colour
+ * source_map: bool - Whether elements are tagged with the source line and procedure that drew them
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvgOptions {
+    pub precision: usize,
+    pub compact: bool,
+    pub source_map: bool,
+}
+
+/**
+ * The data-line and data-proc attributes linking an SVG element to the statement (and procedure) that drew it
+ */
+fn svg_source_attributes(segment: &Segment, calls: &[ProcedureCall]) -> String {
+    let mut attributes = String::new();
+
+    if segment.line > 0 {
+        let _ = write!(attributes, " data-line=\"{}\"", segment.line);
+    }
+    if let Some(call) = segment.call {
+        let _ = write!(attributes, " data-proc=\"{}\"", calls[call].name);
+    }
+
+    attributes
+}
+
+/**
+ * Represents when each line of a self-animating SVG is drawn, with every line taking as long to appear as it takes
+ * to draw it at a constant speed
+ *
+ * Properties:
+ * drawn: Vec<f64> - The length of line drawn before each segment, followed by the total length drawn
+ * duration: f64 - The time (s) the whole drawing takes
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgTimeline {
+    drawn: Vec<f64>,
+    duration: f64,
+}
+
+impl SvgTimeline {
+    /**
+     * The timeline of drawing the given segments at the given speed (pixels per second)
+     */
+    pub fn new(segments: &[Segment], speed: f64) -> Self {
+        let drawn: Vec<f64> = std::iter::once(0.0)
+            .chain(segments.iter().scan(0.0, |drawn, segment| {
+                if segment.pen_down {
+                    *drawn += segment.length();
+                }
+                Some(*drawn)
+            }))
+            .collect();
+        let duration = drawn[drawn.len() - 1] / speed;
+
+        SvgTimeline { drawn, duration }
+    }
+
+    fn total(&self) -> f64 {
+        self.drawn[self.drawn.len() - 1]
+    }
+
+    /**
+     * The fraction of the animation that has passed once the given number of segments have been drawn
+     */
+    fn time(&self, segments: usize) -> f64 {
+        (self.drawn[segments] / self.total()).min(1.0)
+    }
+
+    /**
+     * The animation of an element made of the given lines, in which each line appears while the turtle draws it
+     *
+     * The element's `stroke-dasharray` grows from nothing to its whole length. A linear animation has to end at a
+     * keyTime of 1, so the drawn element is held until then
+     */
+    fn line_animation(&self, lines: &[(usize, &Segment)]) -> Option<String> {
+        let length: f64 = lines.iter().map(|(_, line)| line.length()).sum();
+        if self.total() <= 0.0 || length <= 0.0 {
+            return None;
+        }
+
+        // Round the dash up so the fully drawn element is never cut short
+        let dash = (length * 1000.0).ceil() / 1000.0;
+
+        let mut values = vec![0.0];
+        let mut times = vec!["0".to_string()];
+        let mut drawn = 0.0;
+        for &(i, line) in lines {
+            if line.length() <= 0.0 {
+                continue;
+            }
+
+            values.push(drawn);
+            times.push(format!("{:.6}", self.time(i)));
+            drawn += line.length();
+            values.push(drawn);
+            times.push(format!("{:.6}", self.time(i + 1)));
+        }
+        values.pop();
+        values.extend([dash, dash]);
+        times.push("1".to_string());
+
+        let values: Vec<String> = values
+            .iter()
+            .map(|&value| format!("{} {}", format_number(value), format_number(dash)))
+            .collect();
+
+        Some(format!(
+            "<animate attributeName=\"stroke-dasharray\" values=\"{}\" keyTimes=\"{}\" dur=\"{:.3}s\" fill=\"freeze\"/>",
+            values.join(";"),
+            times.join(";"),
+            self.duration
+        ))
+    }
+
+    /**
+     * The animation of a shape filled with a gradient, which appears once its FILLED block has finished
+     */
+    fn fill_animation(&self, fill: &Fill) -> Option<String> {
+        if self.total() <= 0.0 || self.drawn[fill.finished] <= 0.0 {
+            return None;
+        }
+
+        Some(format!(
+            "<animate attributeName=\"fill-opacity\" values=\"0;1\" keyTimes=\"0;{:.6}\" calcMode=\"discrete\" dur=\"{:.3}s\" fill=\"freeze\"/>",
+            self.time(fill.finished),
+            self.duration
+        ))
+    }
+}
+
+/**
+ * Write an SVG element with the given attributes, containing its animation (if any)
+ */
+fn write_element(
+    output: &mut String,
+    indent: usize,
+    name: &str,
+    attributes: &str,
+    animation: Option<String>,
+) {
+    let indent = " ".repeat(indent);
+
+    let _ = match animation {
+        Some(animation) => writeln!(
+            output,
+            "{0}<{1} {2}>\n{0}    {3}\n{0}</{1}>",
+            indent, name, attributes, animation
+        ),
+        None => writeln!(output, "{}<{} {}/>", indent, name, attributes),
+    };
+}
+
+/**
+ * The SVG elements for a run of painted lines (given with their index), with the lines of each colour grouped
+ * together and connected lines joined into a single polyline (or path, if it has curves)
+ *
+ * Colours are grouped in the order they first appear. Lines count as connected when their ends are written as the
+ * same coordinates, so a lower precision joins more of them. With a source map, only lines drawn by the same
+ * statement in the same procedure call are grouped, so every group can be tagged with where it came from
+ */
+fn svg_polylines(
+    segments: &[(usize, &Segment)],
+    precision: usize,
+    source_map: Option<&[ProcedureCall]>,
+    timeline: Option<&SvgTimeline>,
+) -> String {
+    let format_number = |value: f64| format_decimal(value, precision);
+
+    let mut groups: Vec<Vec<(usize, &Segment)>> = Vec::new();
+    for &(i, segment) in segments {
+        let same_group = |group: &&mut Vec<(usize, &Segment)>| {
+            let first = group[0].1;
+            (first.rgb(), first.clip) == (segment.rgb(), segment.clip)
+                && (source_map.is_none()
+                    || (first.line, first.call) == (segment.line, segment.call))
+        };
+
+        match groups.iter_mut().find(same_group) {
+            Some(group) => group.push((i, segment)),
+            None => groups.push(vec![(i, segment)]),
+        }
+    }
+
+    let mut output = String::new();
+    for group in groups {
+        let first = group[0].1;
+        let clip = match first.clip {
+            Some(index) => format!(" clip-path=\"url(#clip{})\"", index),
+            None => String::new(),
+        };
+        let source = match source_map {
+            Some(calls) => svg_source_attributes(first, calls),
+            None => String::new(),
+        };
+        let _ = writeln!(
+            output,
+            "    <g fill=\"none\" stroke=\"{}\"{}{}>",
+            rgb_hex(first.rgb()),
+            clip,
+            source
+        );
+
+        let mut runs: Vec<Vec<(usize, &Segment)>> = Vec::new();
+        let mut end: Option<(String, String)> = None;
+        for (i, segment) in group {
+            let start = Some((format_number(segment.x1), format_number(segment.y1)));
+
+            match runs.last_mut() {
+                Some(run) if start == end => run.push((i, segment)),
+                _ => runs.push(vec![(i, segment)]),
+            }
+            end = Some((format_number(segment.x2), format_number(segment.y2)));
+        }
+
+        for run in runs {
+            let animation = timeline.and_then(|timeline| timeline.line_animation(&run));
+            let (first, segments) = (run[0].1, run.iter().map(|&(_, segment)| segment));
+
+            if run.iter().all(|(_, segment)| segment.controls.is_none()) {
+                let points: Vec<String> = std::iter::once((first.x1, first.y1))
+                    .chain(segments.map(|segment| (segment.x2, segment.y2)))
+                    .map(|(x, y)| format!("{},{}", format_number(x), format_number(y)))
+                    .collect();
+
+                let attributes = format!("points=\"{}\"", points.join(" "));
+                write_element(&mut output, 8, "polyline", &attributes, animation);
+            } else {
+                let mut path = format!("M {} {}", format_number(first.x1), format_number(first.y1));
+                for segment in segments {
+                    if let Some((cx1, cy1, cx2, cy2)) = segment.controls {
+                        let _ = write!(
+                            path,
+                            " C {} {} {} {}",
+                            format_number(cx1),
+                            format_number(cy1),
+                            format_number(cx2),
+                            format_number(cy2)
+                        );
+                    } else {
+                        path.push_str(" L");
+                    }
+                    let _ = write!(
+                        path,
+                        " {} {}",
+                        format_number(segment.x2),
+                        format_number(segment.y2)
+                    );
+                }
+
+                let attributes = format!("d=\"{}\"", path);
+                write_element(&mut output, 8, "path", &attributes, animation);
+            }
+        }
+
+        let _ = writeln!(output, "    </g>");
+    }
+
+    output
+}
+
+/**
+ * Generate an SVG image of every drawn line
+ *
+ * With compact output, painted lines are written as polylines grouped by colour (see `svg_polylines`). Erasing and
+ * reversing lines, and gradient fills, depend on what was drawn before them, so lines are never grouped across them
+ *
+ * With a source map, every element is tagged with the source line (data-line) and innermost procedure (data-proc)
+ * that drew it, and everything drawn by a top-level procedure call is wrapped in a group tagged with the procedure's
+ * name and the line of the call
+ *
+ * With a timeline, every element contains a SMIL animation that reveals it as the turtle draws it, so viewers
+ * without animation support (and the end of the animation) show the same image as without one
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * view: &View - The region of the canvas to show
+ * calls: &[ProcedureCall] - The procedure calls the segments were made in
+ * options: &SvgOptions - The precision, compactness and source mapping of the output
+ * timeline: Option<&SvgTimeline> - When each line is drawn, if the SVG animates itself
+ *
+ * Returns:
+ * String - The SVG file contents
+ */
+pub fn to_svg(
+    segments: &[Segment],
+    view: &View,
+    calls: &[ProcedureCall],
+    options: &SvgOptions,
+    timeline: Option<&SvgTimeline>,
+) -> String {
+    let source_map = options.source_map.then_some(calls);
+    let polylines = |batch: &mut Vec<(usize, &Segment)>| {
+        svg_polylines(
+            &std::mem::take(batch),
+            options.precision,
+            source_map,
+            timeline,
+        )
+    };
+    let write_fill = |output: &mut String, index: usize, fill: &Fill| {
+        let attributes = svg_fill_attributes(fill, index, options.precision);
+        let animation = timeline.and_then(|timeline| timeline.fill_animation(fill));
+        write_element(output, 4, "path", &attributes, animation);
+    };
+
+    let mut output = svg_header(view);
+    let mut fills = view.fills.iter().enumerate().peekable();
+    let mut batch: Vec<(usize, &Segment)> = Vec::new();
+    let mut open_call: Option<usize> = None;
+
+    for (i, segment) in segments.iter().enumerate() {
+        // Gradient fills go underneath the lines drawn after them
+        if fills.peek().is_some_and(|(_, fill)| fill.segments <= i) {
+            output.push_str(&polylines(&mut batch));
+        }
+        while let Some((index, fill)) = fills.next_if(|(_, fill)| fill.segments <= i) {
+            write_fill(&mut output, index, fill);
+        }
+
+        if !segment.pen_down {
+            continue;
+        }
+
+        let top_level = source_map.and_then(|calls| Some(calls[segment.call?].top_level));
+        if top_level != open_call {
+            output.push_str(&polylines(&mut batch));
+
+            if open_call.is_some() {
+                let _ = writeln!(output, "    </g>");
+            }
+            if let Some(top_level) = top_level {
+                let call = &calls[top_level];
+                let _ = writeln!(
+                    output,
+                    "    <g data-proc=\"{}\" data-line=\"{}\">",
+                    call.name, call.line
+                );
+            }
+            open_call = top_level;
+        }
+
+        if options.compact && segment.mode == PenMode::Paint {
+            batch.push((i, segment));
+            continue;
+        }
+
+        output.push_str(&polylines(&mut batch));
+
+        let source = match source_map {
+            Some(calls) => svg_source_attributes(segment, calls),
+            None => String::new(),
+        };
+        let attributes = format!(
+            "{}{}",
+            svg_line_attributes(segment, view.background, options.precision),
+            source
+        );
+        let animation = timeline.and_then(|timeline| timeline.line_animation(&[(i, segment)]));
+        write_element(&mut output, 4, "path", &attributes, animation);
+    }
+
+    output.push_str(&polylines(&mut batch));
+    if open_call.is_some() {
+        let _ = writeln!(output, "    </g>");
+    }
+
+    for (index, fill) in fills {
+        write_fill(&mut output, index, fill);
+    }
+
+    output.push_str("</svg>\n");
+
+    output
+}
+
+/*
+ * Raster
+ */
+
+/**
+ * Convert a pen color (0-15) into a tiny-skia color
+ */
+pub fn skia_color(color: i32) -> Color {
+    skia_rgb(palette_rgb(color))
+}
+
+/**
+ * Convert a colour given as (red, green, blue) into a tiny-skia color
+ */
+pub fn skia_rgb((red, green, blue): (u8, u8, u8)) -> Color {
+    Color::from_rgba8(red, green, blue, 255)
+}
+
+/**
+ * Create an empty raster image of the given view, filled with the background and underlay
+ */
+pub fn create_pixmap(view: &View) -> Result<Pixmap, String> {
+    let mut pixmap = Pixmap::new(view.output_width, view.output_height)
+        .ok_or("image dimensions must be non-zero".to_string())?;
+    pixmap.fill(skia_color(view.background));
+
+    if let Some(underlay) = &view.underlay {
+        let transform = view.transform().pre_scale(
+            underlay.width as f32 / underlay.pixmap.width() as f32,
+            underlay.height as f32 / underlay.pixmap.height() as f32,
+        );
+        let paint = PixmapPaint {
+            quality: FilterQuality::Bilinear,
+            ..PixmapPaint::default()
+        };
+
+        pixmap.draw_pixmap(0, 0, underlay.pixmap.as_ref(), &paint, transform, None);
+    }
+
+    Ok(pixmap)
+}
+
+/**
+ * Create a mask for each clipping region of the given view, covering the output image pixels inside it
+ */
+pub fn clip_masks(view: &View) -> Result<Vec<Mask>, String> {
+    view.clips
+        .iter()
+        .map(|polygon| {
+            let mut mask = Mask::new(view.output_width, view.output_height)
+                .ok_or("image dimensions must be non-zero".to_string())?;
+
+            let mut path = PathBuilder::new();
+            for (i, &(x, y)) in polygon.iter().enumerate() {
+                if i == 0 {
+                    path.move_to(x as f32, y as f32);
+                } else {
+                    path.line_to(x as f32, y as f32);
+                }
+            }
+            path.close();
+
+            // A region with no area hides everything drawn in it
+            if let Some(path) = path.finish() {
+                mask.fill_path(&path, FillRule::Winding, true, view.transform());
+            }
+
+            Ok(mask)
+        })
+        .collect()
+}
+
+/**
+ * Draw a single line onto a raster image, identically to how its SVG path element is rendered
+ *
+ * Lines drawn in a clipping region are masked by the matching entry of `masks` (see `clip_masks`)
+ */
+pub fn draw_segment(
+    pixmap: &mut Pixmap,
+    segment: &Segment,
+    background: i32,
+    transform: Transform,
+    masks: &[Mask],
+) {
+    let mut path = PathBuilder::new();
+    path.move_to(segment.x1 as f32, segment.y1 as f32);
+    match segment.controls {
+        Some((cx1, cy1, cx2, cy2)) => path.cubic_to(
+            cx1 as f32,
+            cy1 as f32,
+            cx2 as f32,
+            cy2 as f32,
+            segment.x2 as f32,
+            segment.y2 as f32,
+        ),
+        None => path.line_to(segment.x2 as f32, segment.y2 as f32),
+    }
+
+    if let Some(path) = path.finish() {
+        let mut paint = Paint::default();
+        paint.set_color(skia_rgb(segment.stroke_rgb(background)));
+
+        if segment.mode == PenMode::Reverse {
+            paint.blend_mode = BlendMode::Difference;
+        }
+
+        let mask = segment.clip.map(|index| &masks[index]);
+        pixmap.stroke_path(&path, &paint, &Stroke::default(), transform, mask);
+    }
+}
+
+/**
+ * Fill a shape with a gradient on a raster image, identically to how its SVG path element is rendered
+ */
+pub fn draw_fill(pixmap: &mut Pixmap, fill: &Fill, transform: Transform, masks: &[Mask]) {
+    let (from, to) = fill.gradient.colors();
+    let stops = vec![
+        GradientStop::new(0.0, skia_color(from)),
+        GradientStop::new(1.0, skia_color(to)),
+    ];
+
+    let shader = match fill.gradient {
+        FillGradient::Linear { angle, .. } => {
+            let ((x1, y1), (x2, y2)) = fill.linear_ends(angle);
+            let (start, end) = (
+                Point::from_xy(x1 as f32, y1 as f32),
+                Point::from_xy(x2 as f32, y2 as f32),
+            );
+
+            LinearGradient::new(start, end, stops, SpreadMode::Pad, Transform::identity())
+        }
+        FillGradient::Radial { .. } => {
+            let ((cx, cy), radius) = fill.radial_circle();
+            let center = Point::from_xy(cx as f32, cy as f32);
+
+            RadialGradient::new(
+                center,
+                center,
+                radius as f32,
+                stops,
+                SpreadMode::Pad,
+                Transform::identity(),
+            )
+        }
+    };
+
+    let mut path = PathBuilder::new();
+    for (i, &(x, y)) in fill.polygon.iter().enumerate() {
+        if i == 0 {
+            path.move_to(x as f32, y as f32);
+        } else {
+            path.line_to(x as f32, y as f32);
+        }
+    }
+    path.close();
+
+    // Shapes with no area (e.g. a line traced back and forth) have nothing to fill
+    if let (Some(path), Some(shader)) = (path.finish(), shader) {
+        let paint = Paint {
+            shader,
+            ..Paint::default()
+        };
+
+        let mask = fill.clip.map(|index| &masks[index]);
+        pixmap.fill_path(&path, &paint, FillRule::Winding, transform, mask);
+    }
+}
+
+/**
+ * Generate a PNG image of every drawn line
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * view: &View - The region of the canvas to show
+ * path: &std::path::Path - The output path of the PNG file
+ *
+ * Returns:
+ * Result<(), String> - Nothing, or a description of the problem
+ */
+pub fn save_png(segments: &[Segment], view: &View, path: &std::path::Path) -> Result<(), String> {
+    let mut pixmap = create_pixmap(view)?;
+    let transform = view.transform();
+    let masks = clip_masks(view)?;
+
+    let mut fills = view.fills.iter().peekable();
+
+    for (i, segment) in segments.iter().enumerate() {
+        while let Some(fill) = fills.next_if(|fill| fill.segments <= i) {
+            draw_fill(&mut pixmap, fill, transform, &masks);
+        }
+
+        if segment.pen_down {
+            draw_segment(&mut pixmap, segment, view.background, transform, &masks);
+        }
+    }
+
+    for fill in fills {
+        draw_fill(&mut pixmap, fill, transform, &masks);
+    }
+
+    pixmap.save_png(path).map_err(|e| e.to_string())
+}
+
+/*
+ * Unit tests
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_view() -> View {
+        View {
+            x: 0.0,
+            y: 0.0,
+            width: 100.0,
+            height: 100.0,
+            output_width: 100,
+            output_height: 100,
+            background: 0,
+            underlay: None,
+            clips: vec![],
+            fills: vec![],
+        }
+    }
+
+    /*
+     * Source maps
+     */
+    fn source_mapped_segments() -> (Vec<Segment>, Vec<ProcedureCall>) {
+        let calls = vec![ProcedureCall {
+            name: "square".to_string(),
+            line: 5,
+            top_level: 0,
+        }];
+        let mut segments = vec![
+            Segment::test_line(0.0, 0.0, 10.0, 0.0, true, 1),
+            Segment::test_line(10.0, 0.0, 10.0, 10.0, true, 1),
+            Segment::test_line(10.0, 10.0, 20.0, 10.0, true, 1),
+        ];
+        segments[0].line = 2;
+        segments[0].call = Some(0);
+        segments[1].line = 3;
+        segments[1].call = Some(0);
+        segments[2].line = 6;
+
+        (segments, calls)
+    }
+
+    #[test]
+    fn test_to_svg_source_map() {
+        let (segments, calls) = source_mapped_segments();
+        let options = SvgOptions {
+            precision: DEFAULT_PRECISION,
+            compact: false,
+            source_map: true,
+        };
+        let expected = [
+            "<svg width=\"100\" height=\"100\" viewBox=\"0 0 100 100\" xmlns=\"http://www.w3.org/2000/svg\">",
+            "    <defs/>",
+            "    <path fill=\"#000000\" stroke=\"none\" d=\"M 0 0 L 100 0 L 100 100 L 0 100 Z\"/>",
+            "    <g data-proc=\"square\" data-line=\"5\">",
+            "    <path fill=\"none\" stroke=\"#0000ff\" d=\"M 0 0 L 10 0\" data-line=\"2\" data-proc=\"square\"/>",
+            "    <path fill=\"none\" stroke=\"#0000ff\" d=\"M 10 0 L 10 10\" data-line=\"3\" data-proc=\"square\"/>",
+            "    </g>",
+            "    <path fill=\"none\" stroke=\"#0000ff\" d=\"M 10 10 L 20 10\" data-line=\"6\"/>",
+            "</svg>\n",
+        ]
+        .join("\n");
+        let result = to_svg(&segments, &test_view(), &calls, &options, None);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_to_svg_source_map_compact() {
+        let (segments, calls) = source_mapped_segments();
+        let options = SvgOptions {
+            precision: DEFAULT_PRECISION,
+            compact: true,
+            source_map: true,
+        };
+        let expected = [
+            "<svg width=\"100\" height=\"100\" viewBox=\"0 0 100 100\" xmlns=\"http://www.w3.org/2000/svg\">",
+            "    <defs/>",
+            "    <path fill=\"#000000\" stroke=\"none\" d=\"M 0 0 L 100 0 L 100 100 L 0 100 Z\"/>",
+            "    <g data-proc=\"square\" data-line=\"5\">",
+            "    <g fill=\"none\" stroke=\"#0000ff\" data-line=\"2\" data-proc=\"square\">",
+            "        <polyline points=\"0,0 10,0\"/>",
+            "    </g>",
+            "    <g fill=\"none\" stroke=\"#0000ff\" data-line=\"3\" data-proc=\"square\">",
+            "        <polyline points=\"10,0 10,10\"/>",
+            "    </g>",
+            "    </g>",
+            "    <g fill=\"none\" stroke=\"#0000ff\" data-line=\"6\">",
+            "        <polyline points=\"10,10 20,10\"/>",
+            "    </g>",
+            "</svg>\n",
+        ]
+        .join("\n");
+        let result = to_svg(&segments, &test_view(), &calls, &options, None);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_to_svg_without_source_map() {
+        let (segments, calls) = source_mapped_segments();
+        let options = SvgOptions {
+            precision: DEFAULT_PRECISION,
+            compact: false,
+            source_map: false,
+        };
+        let result = to_svg(&segments, &test_view(), &calls, &options, None);
+
+        assert!(!result.contains("data-line"));
+        assert!(!result.contains("data-proc"));
+    }
+
+    /*
+     * Compact output and precision
+     */
+    fn compact_segments() -> Vec<Segment> {
+        vec![
+            Segment::test_line(0.0, 0.0, 10.123, 0.0, true, 1),
+            Segment::test_line(10.123, 0.0, 10.123, 5.56, true, 1),
+            Segment::test_line(10.123, 5.56, 20.0, 20.0, false, 1),
+            Segment::test_line(20.0, 20.0, 30.0, 20.0, true, 2),
+            Segment::test_line(30.0, 20.0, 30.0, 30.0, true, 1),
+        ]
+    }
+
+    #[test]
+    fn test_to_svg_compact() {
+        let options = SvgOptions {
+            precision: 1,
+            compact: true,
+            source_map: false,
+        };
+        let expected = [
+            "<svg width=\"100\" height=\"100\" viewBox=\"0 0 100 100\" xmlns=\"http://www.w3.org/2000/svg\">",
+            "    <defs/>",
+            "    <path fill=\"#000000\" stroke=\"none\" d=\"M 0 0 L 100 0 L 100 100 L 0 100 Z\"/>",
+            "    <g fill=\"none\" stroke=\"#0000ff\">",
+            "        <polyline points=\"0,0 10.1,0 10.1,5.6\"/>",
+            "        <polyline points=\"30,20 30,30\"/>",
+            "    </g>",
+            "    <g fill=\"none\" stroke=\"#00ffff\">",
+            "        <polyline points=\"20,20 30,20\"/>",
+            "    </g>",
+            "</svg>\n",
+        ]
+        .join("\n");
+        let result = to_svg(&compact_segments(), &test_view(), &[], &options, None);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_to_svg_precision() {
+        let options = SvgOptions {
+            precision: 1,
+            compact: false,
+            source_map: false,
+        };
+        let expected = [
+            "<svg width=\"100\" height=\"100\" viewBox=\"0 0 100 100\" xmlns=\"http://www.w3.org/2000/svg\">",
+            "    <defs/>",
+            "    <path fill=\"#000000\" stroke=\"none\" d=\"M 0 0 L 100 0 L 100 100 L 0 100 Z\"/>",
+            "    <path fill=\"none\" stroke=\"#0000ff\" d=\"M 0 0 L 10.1 0\"/>",
+            "    <path fill=\"none\" stroke=\"#0000ff\" d=\"M 10.1 0 L 10.1 5.6\"/>",
+            "    <path fill=\"none\" stroke=\"#00ffff\" d=\"M 20 20 L 30 20\"/>",
+            "    <path fill=\"none\" stroke=\"#0000ff\" d=\"M 30 20 L 30 30\"/>",
+            "</svg>\n",
+        ]
+        .join("\n");
+        let result = to_svg(&compact_segments(), &test_view(), &[], &options, None);
+
+        assert_eq!(result, expected);
+    }
+
+    /*
+     * Animation
+     */
+    fn animated_segments() -> Vec<Segment> {
+        vec![
+            Segment::test_line(0.0, 0.0, 10.0, 0.0, true, 1),
+            Segment::test_line(10.0, 0.0, 10.0, 30.0, false, 1),
+            Segment::test_line(10.0, 30.0, 40.0, 30.0, true, 1),
+        ]
+    }
+
+    #[test]
+    fn test_svg_timeline_line_animation() {
+        let segments = animated_segments();
+        let timeline = SvgTimeline::new(&segments, 10.0);
+        let expected = Some(
+            "<animate attributeName=\"stroke-dasharray\" values=\"0 30;0 30;30 30;30 30\" keyTimes=\"0;0.250000;1.000000;1\" dur=\"4.000s\" fill=\"freeze\"/>"
+                .to_string(),
+        );
+        let result = timeline.line_animation(&[(2, &segments[2])]);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_to_svg_animated_key_times() {
+        let segments = animated_segments();
+        let timeline = SvgTimeline::new(&segments, 10.0);
+        let options = SvgOptions {
+            precision: DEFAULT_PRECISION,
+            compact: false,
+            source_map: false,
+        };
+        let result = to_svg(&segments, &test_view(), &[], &options, Some(&timeline));
+
+        // Every animation has as many values as key times, which rise from 0 to 1
+        for animation in result.split("<animate ").skip(1) {
+            let attribute = |name: &str| {
+                let start = animation.find(&format!("{}=\"", name)).unwrap() + name.len() + 2;
+                let end = start + animation[start..].find('"').unwrap();
+                animation[start..end].split(';').collect::<Vec<&str>>()
+            };
+            let values = attribute("values");
+            let times: Vec<f64> = attribute("keyTimes")
+                .iter()
+                .map(|time| time.parse().unwrap())
+                .collect();
+
+            assert_eq!(values.len(), times.len());
+            assert_eq!(times.first(), Some(&0.0));
+            assert_eq!(times.last(), Some(&1.0));
+            assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+    }
+}
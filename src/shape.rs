@@ -0,0 +1,141 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * shape.rs - Shapes drawn by STAMP and SHOWTURTLE
+ * ========================================================================
+ */
+
+/**
+ * Size (pixels) of the built-in shapes, matching the animation cursor
+ */
+const SHAPE_SIZE: f64 = 10.0;
+
+/**
+ * Distance of the Bézier control points from the ends of a quarter circle, as a fraction of the radius
+ */
+const CIRCLE_CONTROL: f64 = 0.5523;
+
+/**
+ * Outline of the built-in turtle shape, as (forward, right) points in sixteenths of the shape size
+ */
+const TURTLE_OUTLINE: [(f64, f64); 22] = [
+    (8.0, 0.0),
+    (6.0, -1.5),
+    (4.0, -2.0),
+    (5.0, -5.0),
+    (3.0, -5.0),
+    (2.0, -4.0),
+    (-2.0, -4.0),
+    (-3.0, -5.0),
+    (-5.0, -5.0),
+    (-4.0, -3.0),
+    (-6.0, -1.0),
+    (-8.0, 0.0),
+    (-6.0, 1.0),
+    (-4.0, 3.0),
+    (-5.0, 5.0),
+    (-3.0, 5.0),
+    (-2.0, 4.0),
+    (2.0, 4.0),
+    (3.0, 5.0),
+    (5.0, 5.0),
+    (4.0, 2.0),
+    (6.0, 1.5),
+];
+
+/**
+ * Represents the shape drawn for the turtle by STAMP and SHOWTURTLE
+ *
+ * Custom shapes are closed polygons, given as (forward, right) points in pixels relative to the turtle
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum TurtleShape {
+    Arrow,
+    Turtle,
+    Circle,
+    Square,
+    Custom(Vec<(f64, f64)>),
+}
+
+/**
+ * Represents one edge of a shape's outline, with points given as (forward, right) distances from the turtle
+ *
+ * Properties:
+ * from: (f64, f64) - The point the edge starts at
+ * controls: Option<((f64, f64), (f64, f64))> - The control points if the edge is a cubic Bézier curve
+ * to: (f64, f64) - The point the edge ends at
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeEdge {
+    pub from: (f64, f64),
+    pub controls: Option<((f64, f64), (f64, f64))>,
+    pub to: (f64, f64),
+}
+
+impl TurtleShape {
+    /**
+     * Look up a built-in shape by its (case-insensitive) name
+     */
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "arrow" => Some(TurtleShape::Arrow),
+            "turtle" => Some(TurtleShape::Turtle),
+            "circle" => Some(TurtleShape::Circle),
+            "square" => Some(TurtleShape::Square),
+            _ => None,
+        }
+    }
+
+    /**
+     * The edges making up the outline of the shape, relative to a turtle pointing forward
+     */
+    pub fn outline(&self) -> Vec<ShapeEdge> {
+        let half = SHAPE_SIZE / 2.0;
+
+        let points: Vec<(f64, f64)> = match self {
+            TurtleShape::Arrow => vec![
+                (half, 0.0),
+                (-half, -SHAPE_SIZE / 3.0),
+                (-half, SHAPE_SIZE / 3.0),
+            ],
+            TurtleShape::Turtle => TURTLE_OUTLINE
+                .iter()
+                .map(|(forward, side)| (forward * SHAPE_SIZE / 16.0, side * SHAPE_SIZE / 16.0))
+                .collect(),
+            TurtleShape::Square => vec![(half, -half), (half, half), (-half, half), (-half, -half)],
+            TurtleShape::Custom(points) => points.clone(),
+            TurtleShape::Circle => {
+                // Four quarter circles, starting ahead of the turtle and going clockwise
+                let quarters = [(half, 0.0), (0.0, half), (-half, 0.0), (0.0, -half)];
+                let control = half * CIRCLE_CONTROL;
+
+                return (0..4)
+                    .map(|i| {
+                        let (from, to) = (quarters[i], quarters[(i + 1) % 4]);
+
+                        ShapeEdge {
+                            from,
+                            controls: Some((
+                                (
+                                    from.0 - from.1 / half * control,
+                                    from.1 + from.0 / half * control,
+                                ),
+                                (to.0 + to.1 / half * control, to.1 - to.0 / half * control),
+                            )),
+                            to,
+                        }
+                    })
+                    .collect();
+            }
+        };
+
+        (0..points.len())
+            .map(|i| ShapeEdge {
+                from: points[i],
+                controls: None,
+                to: points[(i + 1) % points.len()],
+            })
+            .collect()
+    }
+}
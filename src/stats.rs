@@ -0,0 +1,288 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * stats.rs - Statistics about the drawing and the program run that made it
+ * ========================================================================
+ */
+
+use crate::player::json_string;
+use crate::render::{bounding_box, format_number};
+use crate::turtle::Segment;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/**
+ * Represents the formats the statistics report can be printed in
+ */
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum StatsFormat {
+    Text,
+    Json,
+}
+
+/**
+ * Represents how much work the evaluator did while running the program
+ *
+ * Properties:
+ * statements: u64 - The number of statements executed, counting every repetition of loops and procedure bodies
+ * procedure_calls: BTreeMap<String, u64> - The number of times each procedure was called, by name
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionCounts {
+    pub statements: u64,
+    pub procedure_calls: BTreeMap<String, u64>,
+}
+
+/**
+ * Represents the measurements of a drawing
+ *
+ * Properties:
+ * ink_length: f64 - The total length (pixels) of every line drawn with the pen down
+ * travel_length: f64 - The total distance (pixels) moved with the pen up, including jumps (e.g. when wrapping)
+ * segments_by_color: BTreeMap<i32, usize> - The number of lines drawn in each pen colour (0-15)
+ * bounds: Option<(f64, f64, f64, f64)> - The smallest rectangle (min_x, min_y, max_x, max_y) containing every line
+ */
+struct DrawingStats {
+    ink_length: f64,
+    travel_length: f64,
+    segments_by_color: BTreeMap<i32, usize>,
+    bounds: Option<(f64, f64, f64, f64)>,
+}
+
+impl DrawingStats {
+    fn measure(segments: &[Segment]) -> Self {
+        let mut stats = DrawingStats {
+            ink_length: 0.0,
+            travel_length: 0.0,
+            segments_by_color: BTreeMap::new(),
+            bounds: bounding_box(segments),
+        };
+
+        let mut position: Option<(f64, f64)> = None;
+        for segment in segments {
+            // Anything that starts away from where the last movement ended was reached by jumping there
+            if let Some((x, y)) = position {
+                stats.travel_length += (segment.x1 - x).hypot(segment.y1 - y);
+            }
+            position = Some((segment.x2, segment.y2));
+
+            if segment.pen_down {
+                stats.ink_length += segment.length();
+                *stats.segments_by_color.entry(segment.color).or_default() += 1;
+            } else {
+                stats.travel_length += segment.length();
+            }
+        }
+
+        stats
+    }
+}
+
+/**
+ * Generate a report of the drawing's measurements and the work done to draw it
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * plotted: Option<&[Segment]> - The lines as reordered by --optimize-paths, whose pen-up travel is also reported
+ * counts: &ExecutionCounts - The statements and procedure calls made by the program
+ * format: StatsFormat - Whether the report is readable text or a JSON object
+ *
+ * Returns:
+ * String - The report, ending in a newline
+ */
+pub fn stats_report(
+    segments: &[Segment],
+    plotted: Option<&[Segment]>,
+    counts: &ExecutionCounts,
+    format: StatsFormat,
+) -> String {
+    let stats = DrawingStats::measure(segments);
+    let segment_count: usize = stats.segments_by_color.values().sum();
+    let optimized_travel = plotted.map(|plotted| DrawingStats::measure(plotted).travel_length);
+
+    let mut output = String::new();
+    match format {
+        StatsFormat::Text => {
+            let _ = writeln!(output, "statements executed: {}", counts.statements);
+            let _ = writeln!(output, "ink length: {} px", format_number(stats.ink_length));
+            let _ = writeln!(
+                output,
+                "travel length: {} px",
+                format_number(stats.travel_length)
+            );
+            if let Some(travel) = optimized_travel {
+                let _ = writeln!(
+                    output,
+                    "optimized travel length: {} px",
+                    format_number(travel)
+                );
+            }
+            let _ = writeln!(output, "segments: {}", segment_count);
+            for (color, count) in &stats.segments_by_color {
+                let _ = writeln!(output, "    color {}: {}", color, count);
+            }
+            match stats.bounds {
+                Some((min_x, min_y, max_x, max_y)) => {
+                    let _ = writeln!(
+                        output,
+                        "bounding box: ({}, {}) to ({}, {})",
+                        format_number(min_x),
+                        format_number(min_y),
+                        format_number(max_x),
+                        format_number(max_y)
+                    );
+                }
+                None => {
+                    let _ = writeln!(output, "bounding box: none");
+                }
+            }
+            let _ = writeln!(
+                output,
+                "procedure calls: {}",
+                counts.procedure_calls.values().sum::<u64>()
+            );
+            for (name, count) in &counts.procedure_calls {
+                let _ = writeln!(output, "    {}: {}", name, count);
+            }
+        }
+        StatsFormat::Json => {
+            let colors: Vec<String> = stats
+                .segments_by_color
+                .iter()
+                .map(|(color, count)| format!("\"{}\":{}", color, count))
+                .collect();
+            let calls: Vec<String> = counts
+                .procedure_calls
+                .iter()
+                .map(|(name, count)| format!("{}:{}", json_string(name), count))
+                .collect();
+            let bounds = match stats.bounds {
+                Some((min_x, min_y, max_x, max_y)) => format!(
+                    "{{\"min_x\":{},\"min_y\":{},\"max_x\":{},\"max_y\":{}}}",
+                    format_number(min_x),
+                    format_number(min_y),
+                    format_number(max_x),
+                    format_number(max_y)
+                ),
+                None => "null".to_string(),
+            };
+            let optimized_travel = match optimized_travel {
+                Some(travel) => format_number(travel),
+                None => "null".to_string(),
+            };
+
+            let _ = writeln!(
+                output,
+                "{{\"statements\":{},\"ink_length\":{},\"travel_length\":{},\"optimized_travel_length\":{},\"segments\":{},\"segments_by_color\":{{{}}},\"bounding_box\":{},\"procedure_calls\":{{{}}}}}",
+                counts.statements,
+                format_number(stats.ink_length),
+                format_number(stats.travel_length),
+                optimized_travel,
+                segment_count,
+                colors.join(","),
+                bounds,
+                calls.join(",")
+            );
+        }
+    }
+
+    output
+}
+
+/*
+ * Unit tests
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * A line to (30, 40), a pen-up move back up to (30, 0), a line to (40, 0) in another colour and a jump to (40, 5)
+     */
+    fn drawing() -> Vec<Segment> {
+        vec![
+            Segment::test_line(0.0, 0.0, 30.0, 40.0, true, 1),
+            Segment::test_line(30.0, 40.0, 30.0, 0.0, false, 1),
+            Segment::test_line(30.0, 0.0, 40.0, 0.0, true, 4),
+            Segment::test_line(40.0, 5.0, 40.0, 5.0, false, 4),
+        ]
+    }
+
+    fn counts() -> ExecutionCounts {
+        ExecutionCounts {
+            statements: 7,
+            procedure_calls: BTreeMap::from([("square".to_string(), 2), ("tri".to_string(), 1)]),
+        }
+    }
+
+    /*
+     * Text
+     */
+    #[test]
+    fn test_stats_report_text() {
+        let expected = "statements executed: 7\n\
+                        ink length: 60 px\n\
+                        travel length: 45 px\n\
+                        segments: 2\n    \
+                        color 1: 1\n    \
+                        color 4: 1\n\
+                        bounding box: (0, 0) to (40, 40)\n\
+                        procedure calls: 3\n    \
+                        square: 2\n    \
+                        tri: 1\n";
+        let result = stats_report(&drawing(), None, &counts(), StatsFormat::Text);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_stats_report_text_empty() {
+        let expected = "statements executed: 0\n\
+                        ink length: 0 px\n\
+                        travel length: 0 px\n\
+                        segments: 0\n\
+                        bounding box: none\n\
+                        procedure calls: 0\n";
+        let result = stats_report(&[], None, &ExecutionCounts::default(), StatsFormat::Text);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_stats_report_text_optimized_travel() {
+        let plotted = vec![
+            Segment::test_line(0.0, 0.0, 30.0, 40.0, true, 1),
+            Segment::test_line(30.0, 40.0, 40.0, 40.0, true, 4),
+        ];
+        let result = stats_report(&drawing(), Some(&plotted), &counts(), StatsFormat::Text);
+
+        assert!(result.contains("\ntravel length: 45 px\noptimized travel length: 0 px\n"));
+    }
+
+    /*
+     * JSON
+     */
+    #[test]
+    fn test_stats_report_json() {
+        let expected = "{\"statements\":7,\"ink_length\":60,\"travel_length\":45,\
+                        \"optimized_travel_length\":null,\"segments\":2,\
+                        \"segments_by_color\":{\"1\":1,\"4\":1},\
+                        \"bounding_box\":{\"min_x\":0,\"min_y\":0,\"max_x\":40,\"max_y\":40},\
+                        \"procedure_calls\":{\"square\":2,\"tri\":1}}\n";
+        let result = stats_report(&drawing(), None, &counts(), StatsFormat::Json);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_stats_report_json_empty() {
+        let expected = "{\"statements\":0,\"ink_length\":0,\"travel_length\":0,\
+                        \"optimized_travel_length\":null,\"segments\":0,\"segments_by_color\":{},\
+                        \"bounding_box\":null,\"procedure_calls\":{}}\n";
+        let result = stats_report(&[], None, &ExecutionCounts::default(), StatsFormat::Json);
+
+        assert_eq!(result, expected);
+    }
+}
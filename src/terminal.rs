@@ -0,0 +1,313 @@
+/* ========================================================================
+ * COMP6991 24T3 Asssignment 1
+ * Mohammad Mayaz Rakib (z5361151)
+ *
+ * terminal.rs - Rendering of the drawing as text in the terminal
+ * ========================================================================
+ */
+
+use crate::turtle::{PenMode, Segment, REVERSE_COLOR};
+
+/**
+ * SGR foreground colour code of each pen colour (0-15), whose background colour code is 10 higher
+ *
+ * The eight pure colours use the bright codes (90-97) and the rest use whichever normal (30-37) or grey code is
+ * closest, as terminals only provide 16 colours and their exact shades vary between themes. Black uses the
+ * terminal's default colour (39), so it stays visible on dark terminals
+ */
+const ANSI_COLORS: [u8; 16] = [
+    39, 94, 96, 92, 91, 95, 93, 97, 31, 37, 32, 36, 91, 35, 33, 90,
+];
+
+/**
+ * Terminal size used when it cannot be detected (e.g. when output is piped)
+ */
+const DEFAULT_TERMINAL_SIZE: (usize, usize) = (80, 24);
+
+/**
+ * Represents the character sets available for drawing in the terminal
+ *
+ * Braille characters have 2x4 dots per character but only one colour, while half blocks have 1x2 pixels per
+ * character that can each have their own colour
+ */
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum TtyMode {
+    Braille,
+    Block,
+}
+
+impl TtyMode {
+    /**
+     * The number of horizontal and vertical pixels in a single character
+     */
+    fn cell_size(&self) -> (usize, usize) {
+        match self {
+            TtyMode::Braille => (2, 4),
+            TtyMode::Block => (1, 2),
+        }
+    }
+}
+
+/**
+ * Detect the number of columns and rows of the terminal
+ *
+ * The COLUMNS and LINES environment variables take precedence over the size reported by the terminal itself
+ */
+fn terminal_size() -> (usize, usize) {
+    let from_env = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|value| *value > 0)
+    };
+
+    let (detected_columns, detected_rows) = detect_terminal_size().unwrap_or(DEFAULT_TERMINAL_SIZE);
+
+    (
+        from_env("COLUMNS").unwrap_or(detected_columns),
+        from_env("LINES").unwrap_or(detected_rows),
+    )
+}
+
+#[cfg(unix)]
+fn detect_terminal_size() -> Option<(usize, usize)> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+
+    // SAFETY: TIOCGWINSZ only writes into the winsize struct passed to it
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+
+    if result == 0 && size.ws_col > 0 && size.ws_row > 0 {
+        Some((size.ws_col as usize, size.ws_row as usize))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn detect_terminal_size() -> Option<(usize, usize)> {
+    None
+}
+
+/**
+ * A grid of coloured pixels, where each pixel holds the pen color (0-15) last drawn over it (if any)
+ */
+struct PixelGrid {
+    width: usize,
+    height: usize,
+    pixels: Vec<Option<i32>>,
+}
+
+impl PixelGrid {
+    fn new(width: usize, height: usize) -> Self {
+        PixelGrid {
+            width,
+            height,
+            pixels: vec![None; width * height],
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<i32> {
+        self.pixels[y * self.width + x]
+    }
+
+    /**
+     * Draw over a single pixel, where painting sets its colour, erasing clears it and reversing toggles it
+     */
+    fn set(&mut self, x: i64, y: i64, color: i32, mode: PenMode) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            let pixel = &mut self.pixels[y as usize * self.width + x as usize];
+
+            *pixel = match (mode, *pixel) {
+                (PenMode::Paint, _) => Some(color),
+                (PenMode::Erase, _) | (PenMode::Reverse, Some(_)) => None,
+                (PenMode::Reverse, None) => Some(REVERSE_COLOR),
+            };
+        }
+    }
+
+    /**
+     * Draw a line between two pixels using Bresenham's algorithm
+     */
+    fn draw_line(&mut self, (x1, y1): (i64, i64), (x2, y2): (i64, i64), color: i32, mode: PenMode) {
+        let (dx, dy) = ((x2 - x1).abs(), -(y2 - y1).abs());
+        let (step_x, step_y) = ((x2 - x1).signum(), (y2 - y1).signum());
+        let (mut x, mut y) = (x1, y1);
+        let mut error = dx + dy;
+
+        loop {
+            self.set(x, y, color, mode);
+
+            if x == x2 && y == y2 {
+                break;
+            }
+
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+}
+
+/**
+ * The ANSI escape sequence for a pen color (0-15), as either the foreground or background colour
+ */
+fn ansi_color(color: i32, background: bool) -> String {
+    let code = ANSI_COLORS[color as usize] + if background { 10 } else { 0 };
+
+    format!("\x1b[{}m", code)
+}
+
+/**
+ * Render the drawing as text with ANSI colours, scaled to fit a terminal of the given size
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * width: u32 - The width of the canvas in pixels
+ * height: u32 - The height of the canvas in pixels
+ * mode: TtyMode - The character set to draw with
+ * columns: usize - The number of characters available on each line
+ * rows: usize - The number of lines available
+ *
+ * Returns:
+ * String - The rendered text, one line per row
+ */
+pub fn to_tty(
+    segments: &[Segment],
+    width: u32,
+    height: u32,
+    mode: TtyMode,
+    columns: usize,
+    rows: usize,
+) -> String {
+    let (cell_width, cell_height) = mode.cell_size();
+
+    // Scale uniformly, so the drawing keeps its shape inside the available characters
+    let scale = ((columns * cell_width) as f64 / width as f64)
+        .min((rows * cell_height) as f64 / height as f64);
+    let grid_width = ((width as f64 * scale).floor() as usize).max(1);
+    let grid_height = ((height as f64 * scale).floor() as usize).max(1);
+
+    let mut grid = PixelGrid::new(grid_width, grid_height);
+    let to_grid = |x: f64, y: f64| ((x * scale).floor() as i64, (y * scale).floor() as i64);
+
+    for segment in segments
+        .iter()
+        .filter(|segment| segment.pen_down)
+        .flat_map(Segment::lines)
+    {
+        grid.draw_line(
+            to_grid(segment.x1, segment.y1),
+            to_grid(segment.x2, segment.y2),
+            // Part way along a gradient, the pen colour is the nearest end of it
+            segment.color,
+            segment.mode,
+        );
+    }
+
+    let mut output = String::new();
+    let reset = "\x1b[0m".to_string();
+
+    for row in 0..grid_height.div_ceil(cell_height) {
+        // Escape sequences are only written when the colours change between characters
+        let mut current_style = reset.clone();
+
+        for column in 0..grid_width.div_ceil(cell_width) {
+            let pixel = |dx: usize, dy: usize| {
+                let (x, y) = (column * cell_width + dx, row * cell_height + dy);
+
+                if x < grid_width && y < grid_height {
+                    grid.get(x, y)
+                } else {
+                    None
+                }
+            };
+
+            let (style, character) = match mode {
+                TtyMode::Braille => {
+                    // Bits of each dot in a braille character, indexed by [dy][dx]
+                    const DOTS: [[u32; 2]; 4] =
+                        [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+                    let mut bits = 0;
+                    let mut color = None;
+
+                    for (dy, dots) in DOTS.iter().enumerate() {
+                        for (dx, dot) in dots.iter().enumerate() {
+                            if let Some(pixel_color) = pixel(dx, dy) {
+                                bits |= dot;
+                                color = Some(pixel_color);
+                            }
+                        }
+                    }
+
+                    match color {
+                        Some(color) => (
+                            ansi_color(color, false),
+                            char::from_u32(0x2800 + bits).unwrap_or(' '),
+                        ),
+                        None => (reset.clone(), ' '),
+                    }
+                }
+                TtyMode::Block => match (pixel(0, 0), pixel(0, 1)) {
+                    (None, None) => (reset.clone(), ' '),
+                    (Some(top), None) => (reset.clone() + &ansi_color(top, false), '\u{2580}'),
+                    (None, Some(bottom)) => {
+                        (reset.clone() + &ansi_color(bottom, false), '\u{2584}')
+                    }
+                    (Some(top), Some(bottom)) => (
+                        ansi_color(top, false) + &ansi_color(bottom, true),
+                        '\u{2580}',
+                    ),
+                },
+            };
+
+            if style != current_style {
+                output.push_str(&style);
+                current_style = style;
+            }
+            output.push(character);
+        }
+
+        output.push_str(&reset);
+        output.push('\n');
+    }
+
+    output
+}
+
+/**
+ * Print the drawing to the terminal, sized to fit the terminal
+ *
+ * Arguments:
+ * segments: &[Segment] - The movements recorded by the turtle, in drawing order
+ * width: u32 - The width of the canvas in pixels
+ * height: u32 - The height of the canvas in pixels
+ * mode: TtyMode - The character set to draw with
+ */
+pub fn print_tty(segments: &[Segment], width: u32, height: u32, mode: TtyMode) {
+    let (columns, rows) = terminal_size();
+
+    // Leave a line free for the shell prompt
+    print!(
+        "{}",
+        to_tty(
+            segments,
+            width,
+            height,
+            mode,
+            columns,
+            rows.saturating_sub(1).max(1)
+        )
+    );
+}
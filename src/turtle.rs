@@ -6,12 +6,462 @@
  * ========================================================================
  */
 
-use unsvg::{get_end_coordinates, Image, COLORS};
+use unsvg::{get_end_coordinates, COLORS};
 
+use crate::animate::{save_animation, AnimationOptions, FrameMarker};
+use crate::clip::clip_segments;
 use crate::error::print_error;
+use crate::export::{to_dxf, to_eps, to_gcode, to_hpgl, to_pdf, ExportOptions};
+use crate::fill::{Fill, FillPattern};
+use crate::optimize::optimize_paths;
+use crate::player::to_html;
+use crate::render::{save_png, to_svg, SvgOptions, Underlay, View};
+use crate::shape::TurtleShape;
+use crate::stats::{stats_report, ExecutionCounts};
+use crate::terminal::print_tty;
 
 use std::fmt;
 
+/**
+ * Approximate length (pixels) of the straight pieces curves are split into for output formats without curves
+ */
+const CURVE_PIECE_LENGTH: f64 = 2.0;
+
+/**
+ * Represents a single straight-line or curved movement of the turtle
+ *
+ * Every movement is recorded (whether or not the pen is down) so that every output format can replay the drawing
+ * in the order it was made
+ *
+ * Properties:
+ * x1: f64 - The x-coordinate the movement started at
+ * y1: f64 - The y-coordinate the movement started at
+ * x2: f64 - The x-coordinate the movement ended at
+ * y2: f64 - The y-coordinate the movement ended at
+ * controls: Option<(f64, f64, f64, f64)> - The control points (x1, y1, x2, y2) if the movement is a cubic Bézier curve
+ * pen_down: bool - Whether the pen was down (i.e. a line was drawn) during the movement
+ * color: i32 - The color of the pen (0-15) during the movement
+ * shade: Option<(u8, u8, u8)> - The exact colour (red, green, blue) of the pen if it was part way along a gradient
+ * mode: PenMode - How the pen drew during the movement
+ * line: usize - The source line of the statement that made the movement (0 if unknown)
+ * clip: Option<usize> - The index of the clipping region the movement was drawn in, if any
+ * call: Option<usize> - The index of the innermost procedure call the movement was made in (see `ProcedureCall`), if
+ * any
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+    pub controls: Option<(f64, f64, f64, f64)>,
+    pub pen_down: bool,
+    pub color: i32,
+    pub shade: Option<(u8, u8, u8)>,
+    pub mode: PenMode,
+    pub line: usize,
+    pub clip: Option<usize>,
+    pub call: Option<usize>,
+}
+
+impl Segment {
+    /**
+     * A straight movement painted in the given pen colour (if the pen is down), for building drawings in tests
+     */
+    #[cfg(test)]
+    pub fn test_line(x1: f64, y1: f64, x2: f64, y2: f64, pen_down: bool, color: i32) -> Self {
+        Segment {
+            x1,
+            y1,
+            x2,
+            y2,
+            controls: None,
+            pen_down,
+            color,
+            shade: None,
+            mode: PenMode::Paint,
+            line: 0,
+            clip: None,
+            call: None,
+        }
+    }
+
+    /**
+     * Whether the movement left a mark in the pen's own colour, i.e. one a physical pen could draw
+     */
+    pub fn is_painted(&self) -> bool {
+        self.pen_down && self.mode == PenMode::Paint
+    }
+
+    /**
+     * The colour (red, green, blue) of the pen during the movement
+     */
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        self.shade.unwrap_or_else(|| palette_rgb(self.color))
+    }
+
+    /**
+     * The colour (red, green, blue) the line is stroked with: the pen colour when painting, the background when
+     * erasing, and white (inverted against what is underneath) when reversing
+     */
+    pub fn stroke_rgb(&self, background: i32) -> (u8, u8, u8) {
+        match self.mode {
+            PenMode::Paint => self.rgb(),
+            PenMode::Erase => palette_rgb(background),
+            PenMode::Reverse => palette_rgb(REVERSE_COLOR),
+        }
+    }
+
+    /**
+     * The point at a fraction t (0-1) of the way along the movement
+     */
+    pub fn point_at(&self, t: f64) -> (f64, f64) {
+        match self.controls {
+            None => (
+                self.x1 + (self.x2 - self.x1) * t,
+                self.y1 + (self.y2 - self.y1) * t,
+            ),
+            Some((cx1, cy1, cx2, cy2)) => {
+                let u = 1.0 - t;
+                let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+
+                (
+                    a * self.x1 + b * cx1 + c * cx2 + d * self.x2,
+                    a * self.y1 + b * cy1 + c * cy2 + d * self.y2,
+                )
+            }
+        }
+    }
+
+    /**
+     * The movement as straight lines, with curves split into short pieces
+     */
+    pub fn lines(&self) -> Vec<Segment> {
+        let (cx1, cy1, cx2, cy2) = match self.controls {
+            Some(controls) => controls,
+            None => return vec![self.clone()],
+        };
+
+        // The control polygon is never shorter than the curve, so the pieces are at most CURVE_PIECE_LENGTH long
+        let polygon_length = (cx1 - self.x1).hypot(cy1 - self.y1)
+            + (cx2 - cx1).hypot(cy2 - cy1)
+            + (self.x2 - cx2).hypot(self.y2 - cy2);
+        let steps = ((polygon_length / CURVE_PIECE_LENGTH).ceil() as usize).clamp(1, 1000);
+
+        let points: Vec<(f64, f64)> = (0..=steps)
+            .map(|i| self.point_at(i as f64 / steps as f64))
+            .collect();
+
+        points
+            .windows(2)
+            .map(|pair| Segment {
+                x1: pair[0].0,
+                y1: pair[0].1,
+                x2: pair[1].0,
+                y2: pair[1].1,
+                controls: None,
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /**
+     * The same movement with every point (including control points) moved by a function
+     */
+    fn mapped(&self, map: impl Fn((f64, f64)) -> (f64, f64)) -> Segment {
+        let (x1, y1) = map((self.x1, self.y1));
+        let (x2, y2) = map((self.x2, self.y2));
+        let controls = self.controls.map(|(cx1, cy1, cx2, cy2)| {
+            let (cx1, cy1) = map((cx1, cy1));
+            let (cx2, cy2) = map((cx2, cy2));
+
+            (cx1, cy1, cx2, cy2)
+        });
+
+        Segment {
+            x1,
+            y1,
+            x2,
+            y2,
+            controls,
+            ..self.clone()
+        }
+    }
+
+    /**
+     * The length of the movement, following curves
+     */
+    pub fn length(&self) -> f64 {
+        self.lines()
+            .iter()
+            .map(|line| (line.x2 - line.x1).hypot(line.y2 - line.y1))
+            .sum()
+    }
+}
+
+/**
+ * Represents a single call of a procedure made while running the program
+ *
+ * Properties:
+ * name: String - The name of the procedure called
+ * line: usize - The source line of the call (0 if unknown)
+ * top_level: usize - The index of the outermost call this call was made inside (its own index if not nested)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcedureCall {
+    pub name: String,
+    pub line: usize,
+    pub top_level: usize,
+}
+
+/**
+ * Color (0-15) that reversing lines are drawn in with a difference blend, which inverts whatever is underneath
+ */
+pub const REVERSE_COLOR: i32 = 7;
+
+/**
+ * The red, green and blue components of a pen colour (0-15)
+ */
+pub fn palette_rgb(color: i32) -> (u8, u8, u8) {
+    let color = COLORS[color as usize];
+
+    (color.red, color.green, color.blue)
+}
+
+/**
+ * Exit with an error if a pen colour is not one of the 16 colours (0-15)
+ */
+pub fn check_pen_color(color: i32) {
+    if !(0..=15).contains(&color) {
+        print_error(
+            "invalid color",
+            &format!("color must be between 0 and 15, got {}", color),
+            &[
+                "ensure the color value is numeric",
+                "ensure color value is between 0 and 15",
+            ],
+            true,
+        );
+    }
+}
+
+/**
+ * The colour (red, green, blue) a fraction t (0-1) of the way between two pen colours (0-15)
+ */
+pub fn blend_rgb(from: i32, to: i32, t: f64) -> (u8, u8, u8) {
+    let ((r1, g1, b1), (r2, g2, b2)) = (palette_rgb(from), palette_rgb(to));
+    let blend = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+
+    (blend(r1, r2), blend(g1, g2), blend(b1, b2))
+}
+
+/**
+ * Represents a pen whose colour changes gradually from one pen colour to another over a number of movements
+ *
+ * Properties:
+ * from: i32 - The colour (0-15) of the first movement
+ * to: i32 - The colour (0-15) reached after `steps` movements, and kept afterwards
+ * steps: u32 - The number of movements the change takes
+ * step: u32 - The number of movements drawn so far
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenGradient {
+    pub from: i32,
+    pub to: i32,
+    pub steps: u32,
+    pub step: u32,
+}
+
+impl PenGradient {
+    /**
+     * The nearest pen colour (0-15) and exact colour (red, green, blue) of the current movement
+     */
+    fn shade(&self) -> (i32, (u8, u8, u8)) {
+        let t = if self.steps <= 1 {
+            1.0
+        } else {
+            (self.step as f64 / (self.steps - 1) as f64).min(1.0)
+        };
+
+        // Formats with a fixed set of pens (e.g. plotters) use whichever end of the gradient is closer
+        let nearest = if t < 0.5 { self.from } else { self.to };
+
+        (nearest, blend_rgb(self.from, self.to, t))
+    }
+}
+
+/**
+ * Represents how the pen draws when it is down
+ *
+ * Paint draws in the pen colour, Erase draws in the background colour, and Reverse inverts whatever it crosses
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PenMode {
+    Paint,
+    Erase,
+    Reverse,
+}
+
+/**
+ * Represents the coordinate system used by XCOR, YCOR, SETX and SETY (and reported by HEADING)
+ *
+ * Screen coordinates have their origin at the top left of the canvas with the y-axis pointing down. Cartesian
+ * coordinates have their origin at the turtle's starting position (the centre of the canvas) with the y-axis pointing
+ * up, as in standard Logo. Headings are compass headings in both, but are reported between 0 and 360 in Cartesian
+ * coordinates
+ */
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum CoordinateSystem {
+    Screen,
+    Cartesian,
+}
+
+/**
+ * Represents what happens when the turtle moves past the edge of the canvas
+ *
+ * Window lets the turtle leave the canvas (its lines are clipped by the output image), Wrap continues the movement
+ * from the opposite edge, and Fence stops the program with an error
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoundaryMode {
+    Window,
+    Wrap,
+    Fence,
+}
+
+/**
+ * Represents how every line the turtle draws is replicated around the centre of the canvas
+ *
+ * Rotational symmetry of order n draws n copies evenly spaced around the centre (1 draws only the original), and
+ * Mirror draws a copy reflected left-to-right
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symmetry {
+    Rotational(u32),
+    Mirror,
+}
+
+/**
+ * Represents a linear transform (scaling, rotation and mirroring) applied to every movement of the turtle
+ *
+ * A movement the turtle makes by (x, y) pixels is drawn as (a * x + b * y, c * x + d * y) pixels on the canvas
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CanvasTransform {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+}
+
+impl CanvasTransform {
+    pub const IDENTITY: CanvasTransform = CanvasTransform {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+    };
+
+    /**
+     * Apply another transform to movements before this one, so it acts in the turtle's transformed space
+     */
+    fn then(self, other: CanvasTransform) -> CanvasTransform {
+        CanvasTransform {
+            a: self.a * other.a + self.b * other.c,
+            b: self.a * other.b + self.b * other.d,
+            c: self.c * other.a + self.d * other.c,
+            d: self.c * other.b + self.d * other.d,
+        }
+    }
+
+    pub fn scaled(self, factor: f64) -> CanvasTransform {
+        self.then(CanvasTransform {
+            a: factor,
+            b: 0.0,
+            c: 0.0,
+            d: factor,
+        })
+    }
+
+    /**
+     * Rotate clockwise by the given number of degrees, as headings do
+     */
+    pub fn rotated(self, degrees: f64) -> CanvasTransform {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+
+        self.then(CanvasTransform {
+            a: cos,
+            b: -sin,
+            c: sin,
+            d: cos,
+        })
+    }
+
+    /**
+     * Flip left and right (horizontal) or up and down (vertical)
+     */
+    pub fn mirrored(self, horizontal: bool) -> CanvasTransform {
+        let (x, y) = if horizontal { (-1.0, 1.0) } else { (1.0, -1.0) };
+
+        self.then(CanvasTransform {
+            a: x,
+            b: 0.0,
+            c: 0.0,
+            d: y,
+        })
+    }
+
+    fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (self.a * x + self.b * y, self.c * x + self.d * y)
+    }
+
+    /**
+     * Undo the transform of a movement on the canvas, giving the movement the turtle made
+     */
+    fn invert(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let determinant = self.a * self.d - self.b * self.c;
+
+        (
+            (self.d * x - self.b * y) / determinant,
+            (self.a * y - self.c * x) / determinant,
+        )
+    }
+}
+
+/**
+ * Represents a snapshot of the turtle's drawing state, saved by PUSHSTATE and restored by POPSTATE (and by the
+ * brackets of an L-system)
+ *
+ * Properties:
+ * x: f64 - The x-coordinate of the turtle
+ * y: f64 - The y-coordinate of the turtle
+ * heading: f64 - The angle of the turtle in degrees
+ * pen_down: bool - The state of the pen (true if down, false if up)
+ * pen_color: i32 - The color of the pen (0-15)
+ * pen_mode: PenMode - How the pen draws when it is down
+ * pen_gradient: Option<PenGradient> - The gradient the pen colour is following, if any
+ * clip: Option<usize> - The clipping region lines are drawn in, if any
+ * shape: TurtleShape - The shape drawn by STAMP and SHOWTURTLE
+ * visible: bool - Whether the turtle's shape is shown
+ * transform: CanvasTransform - The scaling, rotation and mirroring applied to every movement
+ * symmetry: Symmetry - How every line drawn is replicated around the centre of the canvas
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurtleState {
+    x: f64,
+    y: f64,
+    heading: f64,
+    pen_down: bool,
+    pen_color: i32,
+    pen_mode: PenMode,
+    pen_gradient: Option<PenGradient>,
+    clip: Option<usize>,
+    shape: TurtleShape,
+    visible: bool,
+    transform: CanvasTransform,
+    symmetry: Symmetry,
+}
+
 /**
  * Represents the state of the relative cursor (aka "turtle") in the Logo language
  *
@@ -23,8 +473,34 @@ use std::fmt;
  * heading: f64 - The angle of the turtle in degrees
  * pen_down: bool - The state of the pen (true if down, false if up)
  * pen_color: i32 - The color of the pen (0-15)
- * image: unsvg::Image - The image object used to draw the turtle's path
+ * pen_mode: PenMode - How the pen draws when it is down
+ * pen_gradient: Option<PenGradient> - The gradient the pen colour follows from one movement to the next, if any
+ * width: u32 - The width of the canvas in pixels
+ * height: u32 - The height of the canvas in pixels
  * image_path: String - The output filename for the SVG file
+ * segments: Vec<Segment> - Every movement made by the turtle, in order
+ * export_options: ExportOptions - Settings for every output format, the canvas, the terminal and statistics
+ * animation_options: AnimationOptions - Settings for the animated output formats
+ * frame_markers: Vec<FrameMarker> - The points during the drawing at which animation frames are captured
+ * segments_since_frame: usize - The number of lines drawn since the last animation frame was captured
+ * source: String - The source code of the program being drawn
+ * source_line: usize - The source line of the statement currently being evaluated (0 if unknown)
+ * calls: Vec<ProcedureCall> - Every procedure call made, in the order they were made
+ * call_stack: Vec<usize> - The indices of the procedure calls currently being evaluated, innermost last
+ * coordinate_system: CoordinateSystem - The coordinate system positions and headings are given in
+ * boundary_mode: BoundaryMode - What happens when the turtle moves past the edge of the canvas
+ * underlay: Option<Underlay> - The reference image drawn underneath SVG/PNG output, if any
+ * saved_states: Vec<TurtleState> - The states saved by PUSHSTATE, most recent last
+ * shape: TurtleShape - The shape drawn by STAMP and SHOWTURTLE
+ * visible: bool - Whether the turtle's shape is drawn at its final position in the output
+ * transform: CanvasTransform - The scaling, rotation and mirroring applied to every movement
+ * symmetry: Symmetry - How every line drawn is replicated around the centre of the canvas
+ * clips: Vec<Vec<(f64, f64)>> - Every clipping region set, as polygons in canvas coordinates
+ * clip: Option<usize> - The index of the clipping region lines are currently drawn in, if any
+ * fill_pattern: FillPattern - The pattern FILLED shapes are filled with
+ * fill_paths: Vec<(usize, Vec<(f64, f64)>)> - The number of segments drawn before each unfinished FILLED block and
+ * the points visited inside it, innermost last
+ * fills: Vec<Fill> - Every FILLED shape filled with a gradient, in drawing order
  */
 pub struct Turtle {
     x: f64,
@@ -32,8 +508,33 @@ pub struct Turtle {
     heading: f64,
     pen_down: bool,
     pen_color: i32,
-    image: unsvg::Image,
+    pen_mode: PenMode,
+    pen_gradient: Option<PenGradient>,
+    width: u32,
+    height: u32,
     image_path: std::path::PathBuf,
+    segments: Vec<Segment>,
+    export_options: ExportOptions,
+    animation_options: AnimationOptions,
+    frame_markers: Vec<FrameMarker>,
+    segments_since_frame: usize,
+    source: String,
+    source_line: usize,
+    calls: Vec<ProcedureCall>,
+    call_stack: Vec<usize>,
+    coordinate_system: CoordinateSystem,
+    boundary_mode: BoundaryMode,
+    underlay: Option<Underlay>,
+    saved_states: Vec<TurtleState>,
+    shape: TurtleShape,
+    visible: bool,
+    transform: CanvasTransform,
+    symmetry: Symmetry,
+    clips: Vec<Vec<(f64, f64)>>,
+    clip: Option<usize>,
+    fill_pattern: FillPattern,
+    fill_paths: Vec<(usize, Vec<(f64, f64)>)>,
+    fills: Vec<Fill>,
 }
 
 impl fmt::Debug for Turtle {
@@ -50,18 +551,111 @@ impl fmt::Debug for Turtle {
 }
 
 impl Turtle {
-    pub fn new(width: u32, height: u32, image_path: std::path::PathBuf) -> Self {
+    pub fn new(
+        width: u32,
+        height: u32,
+        image_path: std::path::PathBuf,
+        export_options: ExportOptions,
+        animation_options: AnimationOptions,
+    ) -> Self {
+        let underlay = export_options.underlay.as_ref().map(|path| {
+            Underlay::load(path, width, height).unwrap_or_else(|error| {
+                print_error(
+                    "failed to load underlay",
+                    &error,
+                    &[
+                        "ensure the underlay path is correct",
+                        "ensure the underlay is a PNG image",
+                    ],
+                    true,
+                );
+                panic!();
+            })
+        });
+
         Turtle {
             x: (width / 2) as f64,
             y: (height / 2) as f64,
             heading: 0.0,
             pen_down: false,
             pen_color: 7,
-            image: Image::new(width, height),
+            pen_mode: PenMode::Paint,
+            pen_gradient: None,
+            width,
+            height,
             image_path,
+            segments: Vec::new(),
+            export_options,
+            animation_options,
+            frame_markers: Vec::new(),
+            segments_since_frame: 0,
+            source: String::new(),
+            source_line: 0,
+            calls: Vec::new(),
+            call_stack: Vec::new(),
+            coordinate_system: CoordinateSystem::Screen,
+            boundary_mode: BoundaryMode::Window,
+            underlay,
+            saved_states: Vec::new(),
+            shape: TurtleShape::Arrow,
+            visible: false,
+            transform: CanvasTransform::IDENTITY,
+            symmetry: Symmetry::Rotational(1),
+            clips: Vec::new(),
+            clip: None,
+            fill_pattern: FillPattern::Hatch {
+                angle: 45.0,
+                spacing: 5.0,
+            },
+            fill_paths: Vec::new(),
+            fills: Vec::new(),
         }
     }
 
+    pub fn set_source(&mut self, source: String) {
+        self.source = source;
+    }
+
+    pub fn set_source_line(&mut self, line: usize) {
+        self.source_line = line;
+    }
+
+    /**
+     * Start attributing movements to a call of the named procedure, made on the current source line
+     */
+    pub fn enter_procedure(&mut self, name: &str) {
+        let index = self.calls.len();
+
+        self.calls.push(ProcedureCall {
+            name: name.to_string(),
+            line: self.source_line,
+            top_level: self.call_stack.first().copied().unwrap_or(index),
+        });
+        self.call_stack.push(index);
+    }
+
+    /**
+     * Go back to attributing movements to the procedure call (if any) that made the one that has just finished
+     */
+    pub fn exit_procedure(&mut self) {
+        self.call_stack.pop();
+    }
+
+    pub fn set_coordinate_system(&mut self, coordinate_system: CoordinateSystem) {
+        self.coordinate_system = coordinate_system;
+    }
+
+    pub fn set_boundary_mode(&mut self, boundary_mode: BoundaryMode) {
+        self.boundary_mode = boundary_mode;
+    }
+
+    /**
+     * The screen position of the Cartesian origin, i.e. the turtle's starting position
+     */
+    fn cartesian_origin(&self) -> (f64, f64) {
+        ((self.width / 2) as f64, (self.height / 2) as f64)
+    }
+
     pub fn penup(&mut self) {
         self.pen_down = false;
     }
@@ -70,10 +664,282 @@ impl Turtle {
         self.pen_down = true;
     }
 
+    /**
+     * Lower the pen and set how it draws (i.e. PENPAINT, PENERASE or PENREVERSE)
+     */
+    pub fn set_pen_mode(&mut self, mode: PenMode) {
+        self.pen_down = true;
+        self.pen_mode = mode;
+    }
+
     pub fn set_pen_color(&mut self, color: i32) {
+        check_pen_color(color);
+
+        self.pen_color = color;
+        self.pen_gradient = None;
+    }
+
+    /**
+     * Change the pen colour gradually from one colour to another over the next `steps` movements drawn
+     */
+    pub fn set_pen_gradient(&mut self, from: i32, to: i32, steps: u32) {
+        check_pen_color(to);
+        self.set_pen_color(from);
+        self.pen_gradient = Some(PenGradient {
+            from,
+            to,
+            steps,
+            step: 0,
+        });
+    }
+
+    pub fn set_shape(&mut self, shape: TurtleShape) {
+        self.shape = shape;
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn transform(&self) -> CanvasTransform {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: CanvasTransform) {
+        self.transform = transform;
+    }
+
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) {
+        self.symmetry = symmetry;
+    }
+
+    pub fn set_fill_pattern(&mut self, pattern: FillPattern) {
+        self.fill_pattern = pattern;
+    }
+
+    /**
+     * Start recording the path of a FILLED shape at the turtle's position
+     */
+    pub fn begin_fill(&mut self) {
+        self.fill_paths
+            .push((self.segments.len(), vec![(self.x, self.y)]));
+    }
+
+    /**
+     * Close the path recorded since the matching `begin_fill` and fill it with strokes in the fill pattern, or with
+     * a gradient drawn underneath the lines drawn since
+     */
+    pub fn end_fill(&mut self) {
+        let (start, polygon) = self.fill_paths.pop().unwrap_or_default();
+
+        // Anything less than a triangle has no inside to fill
+        if polygon.len() < 3 {
+            return;
+        }
+
+        if let FillPattern::Gradient(gradient) = self.fill_pattern {
+            let copies: Vec<Vec<(f64, f64)>> = (1..=self.symmetry_copies())
+                .map(|copy| {
+                    polygon
+                        .iter()
+                        .map(|&point| self.symmetry_copy(copy, point))
+                        .collect()
+                })
+                .collect();
+
+            let fills: Vec<Fill> = std::iter::once(polygon)
+                .chain(copies)
+                .map(|polygon| Fill {
+                    polygon,
+                    gradient,
+                    segments: start,
+                    finished: self.segments.len(),
+                    clip: self.clip,
+                })
+                .collect();
+
+            // Fills stay in drawing order, so an enclosing FILLED block (which finishes last) goes underneath
+            let index = self.fills.partition_point(|fill| fill.segments <= start);
+            self.fills.splice(index..index, fills);
+
+            self.count_movement();
+            return;
+        }
+
+        for (from, to) in self.fill_pattern.strokes(&polygon) {
+            self.record_segment(Segment {
+                x1: from.0,
+                y1: from.1,
+                x2: to.0,
+                y2: to.1,
+                controls: None,
+                pen_down: true,
+                color: self.pen_color,
+                shade: None,
+                mode: self.pen_mode,
+                line: self.source_line,
+                call: self.call_stack.last().copied(),
+                clip: self.clip,
+            });
+        }
+
+        self.count_movement();
+    }
+
+    /**
+     * Add points the turtle moved through to the paths of any unfinished FILLED shapes
+     */
+    fn trace_fill(&mut self, points: &[(f64, f64)]) {
+        for (_, path) in &mut self.fill_paths {
+            path.extend_from_slice(points);
+        }
+    }
+
+    /**
+     * Discard everything drawn from now on outside a polygon, with corners given in the current coordinate system
+     */
+    pub fn set_clip(&mut self, corners: &[(f64, f64)]) {
+        let polygon = corners
+            .iter()
+            .map(|&(x, y)| match self.coordinate_system {
+                CoordinateSystem::Screen => (x, y),
+                CoordinateSystem::Cartesian => {
+                    let (origin_x, origin_y) = self.cartesian_origin();
+                    (origin_x + x, origin_y - y)
+                }
+            })
+            .collect();
+
+        self.clips.push(polygon);
+        self.clip = Some(self.clips.len() - 1);
+    }
+
+    pub fn clear_clip(&mut self) {
+        self.clip = None;
+    }
+
+    /**
+     * Draw the outline of the turtle's shape at its position, rotated to its heading, whether or not the pen is down
+     */
+    pub fn stamp(&mut self) {
+        self.push_shape();
+        self.count_movement();
+    }
+
+    /**
+     * Draw the turtle's shape at its final position if SHOWTURTLE is in effect, so it appears in the output
+     */
+    pub fn draw_visible_turtle(&mut self) {
+        if self.visible {
+            self.push_shape();
+        }
+    }
+
+    fn push_shape(&mut self) {
+        let position = (self.x, self.y);
+
+        for edge in self.shape.outline() {
+            let from = self.relative_point(position, edge.from.0, edge.from.1);
+            let to = self.relative_point(position, edge.to.0, edge.to.1);
+            let controls = edge.controls.map(|(control1, control2)| {
+                let control1 = self.relative_point(position, control1.0, control1.1);
+                let control2 = self.relative_point(position, control2.0, control2.1);
+
+                (control1.0, control1.1, control2.0, control2.1)
+            });
+
+            self.record_segment(Segment {
+                x1: from.0,
+                y1: from.1,
+                x2: to.0,
+                y2: to.1,
+                controls,
+                pen_down: true,
+                color: self.pen_color,
+                shade: None,
+                mode: self.pen_mode,
+                line: self.source_line,
+                call: self.call_stack.last().copied(),
+                clip: self.clip,
+            });
+        }
+    }
+
+    /**
+     * A snapshot of the position, heading, pen, clipping region, shape, visibility, transform and symmetry of the
+     * turtle
+     */
+    pub fn save_state(&self) -> TurtleState {
+        TurtleState {
+            x: self.x,
+            y: self.y,
+            heading: self.heading,
+            pen_down: self.pen_down,
+            pen_color: self.pen_color,
+            pen_mode: self.pen_mode,
+            pen_gradient: self.pen_gradient,
+            clip: self.clip,
+            shape: self.shape.clone(),
+            visible: self.visible,
+            transform: self.transform,
+            symmetry: self.symmetry,
+        }
+    }
+
+    /**
+     * Return the turtle to a snapshot taken by `save_state`, moving the turtle without drawing
+     */
+    pub fn restore_state(&mut self, state: TurtleState) {
+        self.x = state.x;
+        self.y = state.y;
+        self.heading = state.heading;
+        self.pen_down = state.pen_down;
+        self.pen_color = state.pen_color;
+        self.pen_mode = state.pen_mode;
+        self.pen_gradient = state.pen_gradient;
+        self.clip = state.clip;
+        self.shape = state.shape;
+        self.visible = state.visible;
+        self.transform = state.transform;
+        self.symmetry = state.symmetry;
+        self.trace_fill(&[(self.x, self.y)]);
+    }
+
+    /**
+     * Save a snapshot of the turtle (see `save_state`) onto the state stack
+     */
+    pub fn push_state(&mut self) {
+        let state = self.save_state();
+        self.saved_states.push(state);
+    }
+
+    /**
+     * Restore the most recently saved state from the state stack, moving the turtle without drawing
+     */
+    pub fn pop_state(&mut self) {
+        let state = match self.saved_states.pop() {
+            Some(state) => state,
+            None => {
+                print_error(
+                    "state stack underflow",
+                    &format!(
+                        "line {}: POPSTATE was called with no saved state",
+                        self.source_line
+                    ),
+                    &["ensure every POPSTATE is preceded by a matching PUSHSTATE"],
+                    true,
+                );
+                panic!();
+            }
+        };
+
+        self.restore_state(state);
+    }
+
+    pub fn set_background(&mut self, color: i32) {
         if !(0..=15).contains(&color) {
             print_error(
-                "invalid color",
+                "invalid background color",
                 &format!("color must be between 0 and 15, got {}", color),
                 &[
                     "ensure the color value is numeric",
@@ -83,46 +949,429 @@ impl Turtle {
             );
         }
 
-        self.pen_color = color;
+        self.export_options.background = color;
     }
 
-    fn move_turtle(&mut self, distance: f64) {
+    /**
+     * Whether a point lies on the canvas, including its edges
+     */
+    fn on_canvas(&self, x: f64, y: f64) -> bool {
+        (0.0..=self.width as f64).contains(&x) && (0.0..=self.height as f64).contains(&y)
+    }
+
+    /**
+     * Bring a turtle that left the canvas before wrapping was turned on back in from the opposite edge
+     */
+    fn wrap_into_canvas(&mut self) {
+        let (width, height) = (self.width as f64, self.height as f64);
+
+        if self.boundary_mode == BoundaryMode::Wrap {
+            if !(0.0..=width).contains(&self.x) {
+                self.x = self.x.rem_euclid(width);
+            }
+            if !(0.0..=height).contains(&self.y) {
+                self.y = self.y.rem_euclid(height);
+            }
+        }
+    }
+
+    /**
+     * Stop the program if the fence is up and a movement between two points passes through a point off the canvas
+     */
+    fn check_fence(
+        &self,
+        (start_x, start_y): (f64, f64),
+        (end_x, end_y): (f64, f64),
+        points: &[(f64, f64)],
+    ) {
+        if self.boundary_mode == BoundaryMode::Fence
+            && !points.iter().all(|(x, y)| self.on_canvas(*x, *y))
+        {
+            print_error(
+                "turtle hit the fence",
+                &format!(
+                    "line {}: moving from ({}, {}) to ({}, {}) would leave the {}x{} canvas",
+                    self.source_line, start_x, start_y, end_x, end_y, self.width, self.height
+                ),
+                &[
+                    "ensure the turtle stays within the canvas",
+                    "use WINDOW or WRAP to allow the turtle to leave the canvas",
+                ],
+                true,
+            );
+        }
+    }
+
+    /**
+     * Count a finished movement towards the next automatically captured animation frame
+     */
+    fn count_movement(&mut self) {
         if self.pen_down {
-            let (new_x, new_y) = match self.image.draw_simple_line(
+            if let Some(gradient) = &mut self.pen_gradient {
+                gradient.step = gradient.step.saturating_add(1);
+            }
+
+            self.segments_since_frame += 1;
+
+            if self.segments_since_frame >= self.animation_options.frame_every.unwrap_or(1) {
+                self.record_frame(None, true);
+            }
+        }
+    }
+
+    fn move_turtle(&mut self, distance: f64) {
+        self.wrap_into_canvas();
+
+        // Lines are drawn between whole pixel coordinates, as they always have been
+        let (start_x, start_y) = ((self.x as i32) as f64, (self.y as i32) as f64);
+
+        let (end_x, end_y) = if self.transform == CanvasTransform::IDENTITY {
+            let (end_x, end_y) = get_end_coordinates(
                 self.x as i32,
                 self.y as i32,
                 self.heading as i32,
                 distance as i32,
-                COLORS[self.pen_color as usize],
-            ) {
-                Ok((x, y)) => (x, y),
-                Err(e) => {
-                    print_error(
-                        "failed to draw line",
-                        &e,
-                        &[
-                            "ensure the distance value is numeric",
-                            "ensure the distance value is positive",
-                        ],
-                        true,
-                    );
-                    panic!();
+            );
+            (end_x as f64, end_y as f64)
+        } else {
+            let angle = ((self.heading as i32) as f64).to_radians();
+            let distance = (distance as i32) as f64;
+            let (dx, dy) = self
+                .transform
+                .apply((distance * angle.sin(), -distance * angle.cos()));
+            ((start_x + dx).round(), (start_y + dy).round())
+        };
+
+        self.check_fence((start_x, start_y), (end_x, end_y), &[(end_x, end_y)]);
+
+        match self.boundary_mode {
+            BoundaryMode::Window | BoundaryMode::Fence => {
+                self.push_segment(start_x, start_y, end_x, end_y);
+                self.x = end_x;
+                self.y = end_y;
+            }
+            BoundaryMode::Wrap => {
+                let (end_x, end_y) = self.wrap_segments(start_x, start_y, end_x, end_y);
+                self.x = end_x;
+                self.y = end_y;
+            }
+        }
+
+        self.trace_fill(&[(self.x, self.y)]);
+        self.count_movement();
+    }
+
+    /**
+     * The canvas position of a point given relative to the turtle, i.e. `forward` pixels ahead of the start point
+     * and `side` pixels to the right of it
+     */
+    fn relative_point(&self, (x, y): (f64, f64), forward: f64, side: f64) -> (f64, f64) {
+        let angle = self.heading.to_radians();
+        let (dx, dy) = self.transform.apply((
+            forward * angle.sin() + side * angle.cos(),
+            -forward * angle.cos() + side * angle.sin(),
+        ));
+
+        (x + dx, y + dy)
+    }
+
+    /**
+     * Draw a cubic Bézier curve, with its control points and end point given relative to the turtle
+     *
+     * Arguments:
+     * control1: (f64, f64) - The first control point, as (forward, right) distances from the turtle
+     * control2: (f64, f64) - The second control point, as (forward, right) distances from the turtle
+     * end: (f64, f64) - The end point, as (forward, right) distances from the turtle
+     */
+    pub fn curve_to(&mut self, control1: (f64, f64), control2: (f64, f64), end: (f64, f64)) {
+        self.wrap_into_canvas();
+        let start = ((self.x as i32) as f64, (self.y as i32) as f64);
+
+        let control1 = self.relative_point(start, control1.0, control1.1);
+        let control2 = self.relative_point(start, control2.0, control2.1);
+        let (end_x, end_y) = self.relative_point(start, end.0, end.1);
+
+        self.draw_curves(
+            start,
+            &[(control1, control2, (end_x.round(), end_y.round()))],
+        );
+    }
+
+    /**
+     * Draw a smooth Catmull-Rom spline from the turtle through a sequence of points given relative to it
+     *
+     * Arguments:
+     * points: &[(f64, f64)] - The points to pass through, as (forward, right) distances from the turtle
+     */
+    pub fn spline(&mut self, points: &[(f64, f64)]) {
+        if points.is_empty() {
+            return;
+        }
+
+        self.wrap_into_canvas();
+        let start = ((self.x as i32) as f64, (self.y as i32) as f64);
+
+        let mut knots = vec![start];
+        knots.extend(
+            points
+                .iter()
+                .map(|&(forward, side)| self.relative_point(start, forward, side)),
+        );
+
+        // The end point is kept on whole pixels, like every other movement
+        if let Some((x, y)) = knots.last_mut() {
+            (*x, *y) = (x.round(), y.round());
+        }
+
+        // Each span between two knots becomes a cubic Bézier curve, with tangents taken from the neighbouring knots
+        let last = knots.len() - 1;
+        let curves: Vec<_> = (0..last)
+            .map(|i| {
+                let (p0, p1, p2, p3) = (
+                    knots[i.saturating_sub(1)],
+                    knots[i],
+                    knots[i + 1],
+                    knots[(i + 2).min(last)],
+                );
+
+                (
+                    (p1.0 + (p2.0 - p0.0) / 6.0, p1.1 + (p2.1 - p0.1) / 6.0),
+                    (p2.0 - (p3.0 - p1.0) / 6.0, p2.1 - (p3.1 - p1.1) / 6.0),
+                    p2,
+                )
+            })
+            .collect();
+
+        self.draw_curves(start, &curves);
+    }
+
+    /**
+     * Record a chain of cubic Bézier curves starting at the given point, then leave the turtle at the end of the
+     * last one, heading along its tangent
+     */
+    #[allow(clippy::type_complexity)]
+    fn draw_curves(&mut self, start: (f64, f64), curves: &[((f64, f64), (f64, f64), (f64, f64))]) {
+        let mut position = start;
+        let mut unwrapped = start;
+        let mut tangent = None;
+
+        for &(control1, control2, end) in curves {
+            let segment = Segment {
+                x1: unwrapped.0,
+                y1: unwrapped.1,
+                x2: end.0,
+                y2: end.1,
+                controls: Some((control1.0, control1.1, control2.0, control2.1)),
+                pen_down: self.pen_down,
+                color: self.pen_color,
+                shade: None,
+                mode: self.pen_mode,
+                line: self.source_line,
+                call: self.call_stack.last().copied(),
+                clip: self.clip,
+            };
+
+            let lines = segment.lines();
+            let points: Vec<(f64, f64)> = lines.iter().map(|line| (line.x2, line.y2)).collect();
+            self.check_fence(unwrapped, end, &points);
+            self.trace_fill(&points);
+
+            let on_canvas = points.iter().all(|(x, y)| self.on_canvas(*x, *y));
+
+            match self.boundary_mode {
+                BoundaryMode::Window | BoundaryMode::Fence => {
+                    self.record_segment(segment);
+                    position = end;
+                }
+                BoundaryMode::Wrap if on_canvas && position == unwrapped => {
+                    self.record_segment(segment);
+                    position = end;
+                }
+                BoundaryMode::Wrap => {
+                    // Curves that cross an edge are split into short lines, each of which wraps around
+                    for line in lines {
+                        position = self.wrap_segments(
+                            position.0,
+                            position.1,
+                            position.0 + line.x2 - line.x1,
+                            position.1 + line.y2 - line.y1,
+                        );
+                    }
+                }
+            }
+
+            // The tangent at the end of a curve points from the last distinct control point to the end point
+            tangent = [control2, control1, unwrapped]
+                .into_iter()
+                .map(|(x, y)| (end.0 - x, end.1 - y))
+                .find(|&(dx, dy)| dx != 0.0 || dy != 0.0)
+                .or(tangent);
+            unwrapped = end;
+        }
+
+        (self.x, self.y) = position;
+
+        if let Some(tangent) = tangent {
+            // Headings start at 0 degrees pointing up and go clockwise, in the turtle's transformed space
+            let (dx, dy) = self.transform.invert(tangent);
+            self.heading = dx.atan2(-dy).to_degrees();
+        }
+
+        self.count_movement();
+    }
+
+    /**
+     * The number of copies made of everything drawn by the symmetry mode, not counting the original
+     */
+    fn symmetry_copies(&self) -> u32 {
+        match self.symmetry {
+            Symmetry::Rotational(order) => order - 1,
+            Symmetry::Mirror => 1,
+        }
+    }
+
+    /**
+     * Where a point drawn on the canvas appears in one of the symmetry mode's copies (numbered from 1)
+     */
+    fn symmetry_copy(&self, copy: u32, (x, y): (f64, f64)) -> (f64, f64) {
+        let (center_x, center_y) = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+
+        match self.symmetry {
+            Symmetry::Rotational(order) => {
+                // Copies go clockwise, like headings
+                let (sin, cos) = (360.0 * copy as f64 / order as f64).to_radians().sin_cos();
+                let (dx, dy) = (x - center_x, y - center_y);
+
+                (
+                    center_x + dx * cos - dy * sin,
+                    center_y + dx * sin + dy * cos,
+                )
+            }
+            Symmetry::Mirror => (2.0 * center_x - x, y),
+        }
+    }
+
+    /**
+     * Record a movement, followed by its copies around the centre of the canvas if symmetry is on
+     *
+     * Lines drawn while a pen gradient is in effect take the gradient's current colour
+     */
+    fn record_segment(&mut self, mut segment: Segment) {
+        if let (Some(gradient), true) = (self.pen_gradient, segment.pen_down) {
+            let (color, shade) = gradient.shade();
+            segment.color = color;
+            segment.shade = Some(shade);
+        }
+
+        let copies: Vec<Segment> = (1..=self.symmetry_copies())
+            .map(|copy| segment.mapped(|point| self.symmetry_copy(copy, point)))
+            .collect();
+
+        self.segments.push(segment);
+        self.segments.extend(copies);
+    }
+
+    fn push_segment(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        self.record_segment(Segment {
+            x1,
+            y1,
+            x2,
+            y2,
+            controls: None,
+            pen_down: self.pen_down,
+            color: self.pen_color,
+            shade: None,
+            mode: self.pen_mode,
+            line: self.source_line,
+            call: self.call_stack.last().copied(),
+            clip: self.clip,
+        });
+    }
+
+    /**
+     * Record a movement that wraps around the edges of the canvas, split into one segment per crossing
+     *
+     * Arguments:
+     * (x1, y1): The start of the movement, within the canvas
+     * (x2, y2): The end of the movement, as if the canvas had no edges
+     *
+     * Returns:
+     * (f64, f64) - The end of the movement, wrapped back into the canvas
+     */
+    fn wrap_segments(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> (f64, f64) {
+        let (width, height) = (self.width as f64, self.height as f64);
+        let (dx, dy) = (x2 - x1, y2 - y1);
+
+        // The unwrapped line is followed from t = 0 to 1, shifting by a whole canvas at every edge crossing
+        let (mut offset_x, mut offset_y) = (0.0, 0.0);
+        let mut start = 0.0;
+
+        loop {
+            let crossing = |delta: f64, position: f64, offset: f64, size: f64| {
+                if delta > 0.0 {
+                    (offset + size - position) / delta
+                } else if delta < 0.0 {
+                    (offset - position) / delta
+                } else {
+                    f64::INFINITY
                 }
             };
 
-            self.x = new_x as f64;
-            self.y = new_y as f64;
-        } else {
-            let (new_x, new_y) = get_end_coordinates(
-                self.x as i32,
-                self.y as i32,
-                self.heading as i32,
-                distance as i32,
-            );    
+            let cross_x = crossing(dx, x1, offset_x, width);
+            let cross_y = crossing(dy, y1, offset_y, height);
+            let end = cross_x.min(cross_y).min(1.0);
+
+            if end > start || (dx == 0.0 && dy == 0.0) {
+                self.push_segment(
+                    x1 + start * dx - offset_x,
+                    y1 + start * dy - offset_y,
+                    x1 + end * dx - offset_x,
+                    y1 + end * dy - offset_y,
+                );
+            }
+
+            if end >= 1.0 {
+                break;
+            }
+
+            if cross_x == end {
+                offset_x += width * dx.signum();
+            }
+            if cross_y == end {
+                offset_y += height * dy.signum();
+            }
+            start = end;
+        }
+
+        (x2 - offset_x, y2 - offset_y)
+    }
 
-            self.x = new_x as f64;
-            self.y = new_y as f64;
+    /**
+     * Capture the current state of the canvas as an animation frame (i.e. at a FRAME or WAIT statement)
+     *
+     * Arguments:
+     * delay: Option<u32> - The time (ms) the frame is shown for, if it overrides the default
+     */
+    pub fn capture_frame(&mut self, delay: Option<u32>) {
+        self.record_frame(delay, false);
+    }
+
+    fn record_frame(&mut self, delay: Option<u32>, automatic: bool) {
+        self.segments_since_frame = 0;
+
+        if !self.animation_options.enabled() {
+            return;
         }
+
+        self.frame_markers.push(FrameMarker {
+            segments: self.segments.len(),
+            x: self.x,
+            y: self.y,
+            heading: self.heading,
+            color: self.pen_color,
+            delay,
+            automatic,
+        });
     }
 
     pub fn forward(&mut self, distance: f64) {
@@ -180,7 +1429,11 @@ impl Turtle {
             }
         } */
 
-        self.x = x;
+        self.x = match self.coordinate_system {
+            CoordinateSystem::Screen => x,
+            CoordinateSystem::Cartesian => self.cartesian_origin().0 + x,
+        };
+        self.trace_fill(&[(self.x, self.y)]);
     }
 
     pub fn set_y(&mut self, y: f64) {
@@ -210,47 +1463,122 @@ impl Turtle {
             }
         } */
 
-        self.y = y;
+        self.y = match self.coordinate_system {
+            CoordinateSystem::Screen => y,
+            CoordinateSystem::Cartesian => self.cartesian_origin().1 - y,
+        };
+        self.trace_fill(&[(self.x, self.y)]);
     }
 
     pub fn xcor(&self) -> f64 {
-        self.x
+        match self.coordinate_system {
+            CoordinateSystem::Screen => self.x,
+            CoordinateSystem::Cartesian => self.x - self.cartesian_origin().0,
+        }
     }
 
     pub fn ycor(&self) -> f64 {
-        self.y
+        match self.coordinate_system {
+            CoordinateSystem::Screen => self.y,
+            CoordinateSystem::Cartesian => self.cartesian_origin().1 - self.y,
+        }
     }
 
     pub fn heading(&self) -> f64 {
-        self.heading
+        match self.coordinate_system {
+            CoordinateSystem::Screen => self.heading,
+            CoordinateSystem::Cartesian => self.heading.rem_euclid(360.0),
+        }
     }
 
     pub fn color(&self) -> i32 {
         self.pen_color
     }
 
+    pub fn is_pen_down(&self) -> bool {
+        self.pen_down
+    }
+
+    pub fn pen_mode(&self) -> PenMode {
+        self.pen_mode
+    }
+
+    /**
+     * The region of the canvas shown in SVG/PNG output
+     *
+     * With --fit the drawing is scaled to fill the output image, and with --infinite the output image is resized to
+     * the drawing, so lines that leave the canvas are never clipped
+     */
+    fn view(&self) -> View {
+        let options = &self.export_options;
+
+        let view = if options.infinite {
+            View::infinite(&self.segments, self.width, self.height, options.margin)
+        } else if options.fit {
+            View::fit(&self.segments, self.width, self.height, options.margin)
+        } else {
+            View::canvas(self.width, self.height)
+        };
+
+        View {
+            background: options.background,
+            underlay: self.underlay.clone(),
+            clips: self.clips.clone(),
+            fills: self.fills.clone(),
+            ..view
+        }
+    }
+
+    /**
+     * The turtle's movements cut down to their clipping regions, for output formats that cannot clip
+     */
+    fn clipped_segments(&self) -> Vec<Segment> {
+        clip_segments(&self.segments, &self.clips)
+    }
+
+    /**
+     * The turtle's movements as a plotter draws them: clipped, and reordered if --optimize-paths is given
+     *
+     * Plotters draw all lines of each colour together, while page formats keep overlapping colours in order (see
+     * `optimize_paths`)
+     */
+    fn plotted_segments(&self, by_color: bool) -> Vec<Segment> {
+        if self.export_options.optimize_paths {
+            optimize_paths(&self.clipped_segments(), by_color)
+        } else {
+            self.clipped_segments()
+        }
+    }
+
+    /**
+     * The settings of SVG output, shared by the static and self-animating SVGs
+     */
+    fn svg_options(&self) -> SvgOptions {
+        SvgOptions {
+            precision: self.export_options.precision,
+            compact: self.export_options.compact_svg,
+            source_map: self.export_options.source_map,
+        }
+    }
+
     pub fn generate_svg(&self) {
         match self.image_path.extension().and_then(|s| s.to_str()) {
             Some("svg") => {
-                let res = self.image.save_svg(&self.image_path);
-                if let Err(error) = res {
-                    print_error(
-                        "failed to generate SVG",
-                        &format!("{:?}", error),
-                        &[
-                            "ensure the output path is correct",
-                            "ensure the output path is writable",
-                        ],
-                        true,
-                    );
-                }
+                let svg = to_svg(
+                    &self.segments,
+                    &self.view(),
+                    &self.calls,
+                    &self.svg_options(),
+                    None,
+                );
+                self.write_output("SVG", svg);
             }
             Some("png") => {
-                let res = self.image.save_png(&self.image_path);
+                let res = save_png(&self.segments, &self.view(), &self.image_path);
                 if let Err(error) = res {
                     print_error(
                         "failed to generate PNG",
-                        &format!("{:?}", error),
+                        &error,
                         &[
                             "ensure the output path is correct",
                             "ensure the output path is writable",
@@ -259,14 +1587,178 @@ impl Turtle {
                     );
                 }
             }
+            Some("gcode") => {
+                let (width, height) = (self.width, self.height);
+                let gcode = to_gcode(
+                    &self.plotted_segments(true),
+                    width,
+                    height,
+                    &self.export_options,
+                );
+                self.write_output("G-code", gcode);
+            }
+            Some("hpgl") => {
+                let (width, height) = (self.width, self.height);
+                let hpgl = to_hpgl(
+                    &self.plotted_segments(true),
+                    width,
+                    height,
+                    &self.export_options,
+                );
+                self.write_output("HPGL", hpgl);
+            }
+            Some("dxf") => {
+                let (width, height) = (self.width, self.height);
+                let dxf = to_dxf(
+                    &self.plotted_segments(true),
+                    width,
+                    height,
+                    &self.export_options,
+                );
+                self.write_output("DXF", dxf);
+            }
+            Some("pdf") => {
+                let (width, height) = (self.width, self.height);
+                let pdf = to_pdf(
+                    &self.plotted_segments(false),
+                    width,
+                    height,
+                    &self.export_options,
+                );
+                self.write_output("PDF", pdf);
+            }
+            Some("eps") => {
+                let (width, height) = (self.width, self.height);
+                let eps = to_eps(
+                    &self.plotted_segments(false),
+                    width,
+                    height,
+                    &self.export_options,
+                );
+                self.write_output("EPS", eps);
+            }
+            Some("html") => {
+                let (width, height) = (self.width, self.height);
+                let html = to_html(
+                    &self.clipped_segments(),
+                    width,
+                    height,
+                    self.export_options.background,
+                    &self.source,
+                );
+                self.write_output("HTML", html);
+            }
             _ => {
                 print_error(
                     "file extension not supported",
-                    "file extension must be either .svg or .png",
-                    &["ensure the output file extension is either .svg or .png"],
+                    "file extension must be one of .svg, .png, .gcode, .hpgl, .dxf, .pdf, .eps or .html",
+                    &["ensure the output file extension is one of .svg, .png, .gcode, .hpgl, .dxf, .pdf, .eps or .html"],
                     true,
                 );
             }
         }
     }
+
+    /**
+     * Print statistics about the drawing and the program run that made it, if requested
+     *
+     * Lines are measured as the program drew them. With --optimize-paths, the pen-up travel of the reordered plot is
+     * reported as well
+     */
+    pub fn generate_stats(&self, counts: &ExecutionCounts) {
+        if let Some(format) = self.export_options.stats {
+            let plotted = self
+                .export_options
+                .optimize_paths
+                .then(|| self.plotted_segments(true));
+
+            print!(
+                "{}",
+                stats_report(&self.segments, plotted.as_deref(), counts, format)
+            );
+        }
+    }
+
+    /**
+     * Print the drawing to the terminal, if requested
+     */
+    pub fn generate_tty(&self) {
+        if let Some(mode) = self.export_options.tty {
+            let (width, height) = (self.width, self.height);
+            print_tty(&self.clipped_segments(), width, height, mode);
+        }
+    }
+
+    /**
+     * Generate the animated GIF and/or PNG frame sequence, if requested
+     *
+     * Frames are captured at every FRAME/WAIT statement, plus every `frame_every` lines if given. A program with
+     * neither gets a frame after every line. The final state of the canvas is always the last frame
+     */
+    pub fn generate_animation(&self) {
+        if !self.animation_options.enabled() {
+            return;
+        }
+
+        let has_explicit_frames = self.frame_markers.iter().any(|marker| !marker.automatic);
+
+        let mut markers: Vec<FrameMarker> = self
+            .frame_markers
+            .iter()
+            .filter(|marker| {
+                !marker.automatic
+                    || !has_explicit_frames
+                    || self.animation_options.frame_every.is_some()
+            })
+            .cloned()
+            .collect();
+
+        if markers.last().map(|marker| marker.segments) != Some(self.segments.len()) {
+            markers.push(FrameMarker {
+                segments: self.segments.len(),
+                x: self.x,
+                y: self.y,
+                heading: self.heading,
+                color: self.pen_color,
+                delay: None,
+                automatic: true,
+            });
+        }
+
+        if let Err(error) = save_animation(
+            &self.segments,
+            &self.calls,
+            &markers,
+            &self.view(),
+            &self.svg_options(),
+            &self.animation_options,
+        ) {
+            print_error(
+                "failed to generate animation",
+                &error,
+                &[
+                    "ensure the animation output paths are correct",
+                    "ensure the animation output paths are writable",
+                ],
+                true,
+            );
+        }
+    }
+
+    /**
+     * Write the contents of a text-based output format to the output path
+     */
+    fn write_output(&self, format: &str, content: impl AsRef<[u8]>) {
+        if let Err(error) = std::fs::write(&self.image_path, content) {
+            print_error(
+                &format!("failed to generate {}", format),
+                &format!("{:?}", error),
+                &[
+                    "ensure the output path is correct",
+                    "ensure the output path is writable",
+                ],
+                true,
+            );
+        }
+    }
 }
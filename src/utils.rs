@@ -10,6 +10,41 @@ use std::fs;
 
 use crate::error::print_error;
 
+/**
+ * A small deterministic pseudo-random number generator (SplitMix64)
+ *
+ * The same seed always produces the same sequence of numbers, so random drawings can be reproduced exactly
+ *
+ * Properties:
+ * state: u64 - The internal state, advanced on every number generated
+ */
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /**
+     * A random number between 0 (inclusive) and the given bound (exclusive), which must be non-zero
+     */
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
 /**
  * Read the content of a file into a string
  *